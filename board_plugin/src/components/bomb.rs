@@ -3,4 +3,7 @@ use bevy::prelude::Component;
 
 /// Bomb component
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Component)]
-pub struct Bomb;
\ No newline at end of file
+pub struct Bomb {
+    /// Number of bombs held by the tile
+    pub count: u8,
+}
\ No newline at end of file
@@ -0,0 +1,5 @@
+use bevy::prelude::Component;
+
+/// Frame drawn around the tile selected by the board cursor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Component)]
+pub struct CursorHighlight;
@@ -0,0 +1,5 @@
+use bevy::prelude::Component;
+
+/// Highlight of the hinted tile
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Component)]
+pub struct HintMarker;
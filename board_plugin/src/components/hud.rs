@@ -0,0 +1,13 @@
+use bevy::prelude::Component;
+
+/// HUD root node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Component)]
+pub struct Hud;
+
+/// HUD text showing the bombs left to flag
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Component)]
+pub struct MineCounterText;
+
+/// HUD text showing the elapsed time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Component)]
+pub struct TimerText;
\ No newline at end of file
@@ -1,13 +1,20 @@
-pub use coordinates::Coordinates;
+pub use minesweeper_core::Coordinates;
 pub use bomb::Bomb;
 pub use bomb_neighbor::BombNeighbor;
-pub use uncover::Uncover;
 pub use pause_cover::PauseCover;
 pub use game_over_cover::GameOverCover;
+pub use victory_cover::VictoryCover;
+pub use hud::{Hud, MineCounterText, TimerText};
+pub use hint_marker::HintMarker;
+pub use probability_marker::ProbabilityMarker;
+pub use cursor_highlight::CursorHighlight;
 
-mod coordinates;
 mod bomb;
 mod bomb_neighbor;
-mod uncover;
 mod pause_cover;
-mod game_over_cover;
\ No newline at end of file
+mod game_over_cover;
+mod victory_cover;
+mod hud;
+mod hint_marker;
+mod probability_marker;
+mod cursor_highlight;
\ No newline at end of file
@@ -0,0 +1,5 @@
+use bevy::prelude::Component;
+
+/// Mine probability tint and text on a tile cover
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Component)]
+pub struct ProbabilityMarker;
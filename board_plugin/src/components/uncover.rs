@@ -1,4 +0,0 @@
-use bevy::prelude::Component;
-
-#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Component)]
-pub struct Uncover;
\ No newline at end of file
@@ -0,0 +1,4 @@
+use bevy::prelude::Component;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Component)]
+pub struct VictoryCover;
\ No newline at end of file
@@ -1,6 +1,7 @@
 use bevy::prelude::Event;
 
 use crate::components::Coordinates;
+use crate::resources::BoardConfigError;
 
 #[derive(Debug, Copy, Clone, Event)]
 pub struct TileTriggerEvent{
@@ -12,6 +13,10 @@ pub struct TileMarkEvent{
     pub coordinates: Coordinates
 }
 
+#[derive(Debug, Copy, Clone, Event)]
+pub struct TileChordEvent{
+    pub coordinates: Coordinates
+}
 
 #[derive(Debug, Copy, Clone, Event)]
 pub struct BoardCompletedEvent;
@@ -19,3 +24,16 @@ pub struct BoardCompletedEvent;
 #[derive(Debug, Copy, Clone, Event)]
 pub struct BombExplosionEvent;
 
+/// Undoes or redoes the last action
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Event)]
+pub enum HistoryEvent {
+    Undo,
+    Redo,
+}
+
+/// Sent instead of creating the board when the board options are invalid
+#[derive(Debug, Clone, Event)]
+pub struct BoardConfigErrorEvent {
+    pub error: BoardConfigError,
+}
+
@@ -0,0 +1,152 @@
+use bevy::prelude::Vec2;
+use minesweeper_core::BoardTopology;
+use crate::components::Coordinates;
+
+/// Height of a pointy top hexagon over its width
+const HEX_HEIGHT_RATIO: f32 = 1.154_700_5; // 2 / sqrt(3)
+/// Distance between two hex rows over the tile width
+const HEX_ROW_SPACING: f32 = 0.866_025_4; // sqrt(3) / 2
+
+/// Center of a tile, from the bottom left corner of the board
+pub fn tile_center(topology: BoardTopology, coordinates: Coordinates, tile_size: f32) -> Vec2 {
+    let x = coordinates.coord_x as f32;
+    let y = coordinates.coord_y as f32;
+    match topology {
+        BoardTopology::Square => Vec2::new(x + 0.5, y + 0.5) * tile_size,
+        BoardTopology::Hex => {
+            let shift = (coordinates.coord_y % 2) as f32 / 2.0;
+            Vec2::new(x + 0.5 + shift, HEX_HEIGHT_RATIO / 2.0 + y * HEX_ROW_SPACING) * tile_size
+        }
+    }
+}
+
+/// Size of a `columns` x `rows` board
+pub fn board_size(topology: BoardTopology, columns: u16, rows: u16, tile_size: f32) -> Vec2 {
+    let columns = columns as f32;
+    let rows = rows as f32;
+    match topology {
+        BoardTopology::Square => Vec2::new(columns, rows) * tile_size,
+        BoardTopology::Hex => {
+            // Odd rows stick out half a tile on the right
+            let width = if rows > 1.0 { columns + 0.5 } else { columns };
+            let height = (rows - 1.0).max(0.0) * HEX_ROW_SPACING + HEX_HEIGHT_RATIO;
+            Vec2::new(width, height) * tile_size
+        }
+    }
+}
+
+/// Size of a tile sprite `width` wide
+pub fn sprite_size(topology: BoardTopology, width: f32) -> Vec2 {
+    match topology {
+        BoardTopology::Square => Vec2::splat(width),
+        BoardTopology::Hex => Vec2::new(width, width * HEX_HEIGHT_RATIO),
+    }
+}
+
+/// Tile under `position`, from the bottom left corner of the board
+pub fn tile_at(
+    topology: BoardTopology,
+    position: Vec2,
+    tile_size: f32,
+    columns: u16,
+    rows: u16,
+) -> Option<Coordinates> {
+    if position.x < 0.0 || position.y < 0.0 {
+        return None;
+    }
+    let in_map = |x: i32, y: i32| {
+        (0..columns as i32).contains(&x) && (0..rows as i32).contains(&y)
+    };
+    match topology {
+        BoardTopology::Square => {
+            let x = (position.x / tile_size) as i32;
+            let y = (position.y / tile_size) as i32;
+            in_map(x, y).then_some(Coordinates { coord_x: x as u16, coord_y: y as u16 })
+        }
+        BoardTopology::Hex => {
+            // The closest row and its neighbors, a hexagon sticks out of its row
+            let row = ((position.y / tile_size - HEX_HEIGHT_RATIO / 2.0) / HEX_ROW_SPACING).round() as i32;
+            (row - 1..=row + 1)
+                .flat_map(|y| {
+                    let column = (position.x / tile_size - (y.rem_euclid(2)) as f32 / 2.0) as i32;
+                    (column - 1..=column + 1).map(move |x| (x, y))
+                })
+                .filter(|(x, y)| in_map(*x, *y))
+                .map(|(x, y)| Coordinates { coord_x: x as u16, coord_y: y as u16 })
+                .find(|coordinates| {
+                    let offset = (position - tile_center(topology, *coordinates, tile_size)) / tile_size;
+                    in_hexagon(offset)
+                })
+        }
+    }
+}
+
+/// Is `offset` from the center of a pointy top hexagon one unit wide inside it?
+fn in_hexagon(offset: Vec2) -> bool {
+    [0.0_f32, 60.0, 120.0].iter().all(|angle| {
+        let normal = Vec2::from_angle(angle.to_radians());
+        offset.dot(normal).abs() <= 0.5
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: f32 = 10.0;
+
+    fn hex_tile_at(x: f32, y: f32) -> Option<Coordinates> {
+        tile_at(BoardTopology::Hex, Vec2::new(x, y), SIZE, 4, 3)
+    }
+
+    #[test]
+    fn square_tiles_are_found_by_position() {
+        let tile_at = |x, y| tile_at(BoardTopology::Square, Vec2::new(x, y), SIZE, 3, 3);
+        assert_eq!(tile_at(15.0, 25.0), Some(Coordinates { coord_x: 1, coord_y: 2 }));
+        assert_eq!(tile_at(-1.0, 5.0), None);
+        assert_eq!(tile_at(35.0, 5.0), None);
+        assert_eq!(tile_at(5.0, 30.5), None);
+    }
+
+    #[test]
+    fn hex_tile_centers_map_back_to_their_tile() {
+        for coord_y in 0..3 {
+            for coord_x in 0..4 {
+                let coordinates = Coordinates { coord_x, coord_y };
+                let center = tile_center(BoardTopology::Hex, coordinates, SIZE);
+                assert_eq!(hex_tile_at(center.x, center.y), Some(coordinates));
+            }
+        }
+    }
+
+    #[test]
+    fn hex_odd_rows_are_shifted_right() {
+        let odd_row_y = tile_center(BoardTopology::Hex, Coordinates { coord_x: 0, coord_y: 1 }, SIZE).y;
+        let even_row_y = tile_center(BoardTopology::Hex, Coordinates { coord_x: 0, coord_y: 0 }, SIZE).y;
+        // The odd row starts half a tile in, and ends half a tile past the even rows
+        assert_eq!(hex_tile_at(2.0, odd_row_y), None);
+        assert_eq!(hex_tile_at(2.0, even_row_y), Some(Coordinates { coord_x: 0, coord_y: 0 }));
+        assert_eq!(hex_tile_at(44.0, odd_row_y), Some(Coordinates { coord_x: 3, coord_y: 1 }));
+        assert_eq!(hex_tile_at(44.0, even_row_y), None);
+    }
+
+    #[test]
+    fn hex_slanted_edges_split_the_rows() {
+        let bottom = tile_center(BoardTopology::Hex, Coordinates { coord_x: 0, coord_y: 0 }, SIZE);
+        let top = tile_center(BoardTopology::Hex, Coordinates { coord_x: 0, coord_y: 1 }, SIZE);
+        // Both sides of the edge shared by the two tiles
+        let middle = (bottom + top) / 2.0;
+        let step = (top - bottom).normalize();
+        assert_eq!(hex_tile_at(middle.x - step.x, middle.y - step.y), Some(Coordinates { coord_x: 0, coord_y: 0 }));
+        assert_eq!(hex_tile_at(middle.x + step.x, middle.y + step.y), Some(Coordinates { coord_x: 0, coord_y: 1 }));
+    }
+
+    #[test]
+    fn hex_positions_out_of_the_board_hit_no_tile() {
+        let size = board_size(BoardTopology::Hex, 4, 3, SIZE);
+        assert_eq!(hex_tile_at(-1.0, 5.0), None);
+        assert_eq!(hex_tile_at(5.0, -1.0), None);
+        assert_eq!(hex_tile_at(size.x + 1.0, 5.0), None);
+        assert_eq!(hex_tile_at(5.0, size.y + 1.0), None);
+    }
+}
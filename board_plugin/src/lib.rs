@@ -1,23 +1,31 @@
 pub mod components;
 pub mod resources;
 mod bounds;
+mod layout;
 mod systems;
-mod events;
+pub mod events;
 
 
 use std::collections::HashMap;
+use std::time::Duration;
 use std::default::{Default};
 use bevy::color::palettes::tailwind;
-use crate::components::{Coordinates, Uncover, PauseCover, GameOverCover};
+use crate::components::{Coordinates, PauseCover, GameOverCover, VictoryCover, Hud};
 use crate::resources::tile::Tile;
 use bevy::prelude::*;
+use bevy::ecs::system::EntityCommands;
 use bevy::state::state::FreelyMutableState;
+use bevy::window::{PrimaryWindow, WindowResized};
+use minesweeper_core::{Game, GameStatus, History};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use resources::tile_map::TileMap;
 use resources::BoardOptions;
 use resources::Board;
 use crate::bounds::Bounds2;
-use crate::events::{BoardCompletedEvent, BombExplosionEvent, TileMarkEvent, TileTriggerEvent};
-use crate::resources::BoardAssets;
+use crate::systems::input::PauseInput;
+use crate::events::{BoardCompletedEvent, BoardConfigErrorEvent, HistoryEvent, BombExplosionEvent, TileChordEvent, TileMarkEvent, TileTriggerEvent};
+use crate::resources::{BoardAssets, BoardSnapshot, BoardCursor, CameraDrag, GameStats, GameTimer, PlayerOptions, ProbabilityOverlay, Replay, ReplayPlayer, ReplayRecorder};
 
 /// White box
 const BACKGROUND_Z: f32 = 0.0;
@@ -30,6 +38,11 @@ const TILE_COVER_Z: f32 = 3.0;
 /// Pause box
 const PAUSE_COVER_Z: f32 = 100.0;
 
+/// Covers displayed once a game ended
+type EndCoverFilter = Or<(With<GameOverCover>, With<VictoryCover>)>;
+/// Covers displayed above the board
+type CoverFilter = Or<(With<PauseCover>, With<GameOverCover>, With<VictoryCover>)>;
+
 pub struct BoardPlugin<T>
 where
     T: FreelyMutableState,
@@ -37,25 +50,116 @@ where
     pub game_state: T,
     pub pause_state: T,
     pub game_over_state: T,
+    pub won_state: T,
 }
 
-impl<T: FreelyMutableState> Plugin for BoardPlugin<T> {
+impl<T> Plugin for BoardPlugin<T>
+where
+    T: FreelyMutableState + Serialize + DeserializeOwned,
+{
     fn build(&self, app: &mut App) {
         
         app
-            .add_systems(OnEnter(self.game_state.clone()), Self::create_board)
+            .add_systems(
+                OnEnter(self.game_state.clone()),
+                (Self::create_board, systems::hud::spawn_hud))
             .add_systems(OnExit(self.game_state.clone()), Self::on_exit_log)
             .add_systems(
                 Update,
                 (
-                    systems::input::input_handling,
+                    // Input is sent before the deferred bombs are placed around the first reveal
+                    (
+                        systems::input::input_handling.before(systems::camera::pan_camera),
+                        systems::cursor::move_cursor,
+                        systems::cursor::cursor_actions.after(systems::cursor::move_cursor),
+                        systems::gamepad::gamepad_cursor,
+                        systems::gamepad::gamepad_actions.after(systems::gamepad::gamepad_cursor),
+                    )
+                        .before(Self::place_deferred_bombs)
+                        .run_if(not(resource_exists::<ReplayPlayer>)),
+                    // The camera moves during replays too
+                    (
+                        systems::camera::pan_camera,
+                        systems::camera::zoom_camera,
+                        systems::camera::reset_camera_input,
+                    ),
+                    systems::cursor::update_cursor_highlight
+                        .after(systems::cursor::move_cursor)
+                        .after(systems::gamepad::gamepad_cursor),
+                    Self::place_deferred_bombs.before(systems::uncover::trigger_event_handler),
                     systems::uncover::trigger_event_handler,
-                    systems::uncover::uncover_tiles,
-                    systems::mark::mark_tiles,
+                    systems::uncover::chord_event_handler,
+                    systems::mark::mark_tiles::<T>,
+                    (systems::hint::clear_hint, systems::hint::show_hint).chain()
+                        .run_if(not(resource_exists::<ReplayPlayer>)),
+                    (
+                        systems::probability::toggle_probability_overlay,
+                        systems::probability::update_probability_overlay,
+                    )
+                        .chain()
+                        .after(systems::uncover::trigger_event_handler)
+                        .after(systems::uncover::chord_event_handler)
+                        .after(systems::mark::mark_tiles::<T>),
                     Self::recreate_board,
-                    Self::pause,
+                    Self::pause.run_if(not(resource_exists::<ReplayPlayer>)),
                     Self::game_over,
-                ).run_if(in_state(self.game_state.clone())))
+                    Self::victory,
+                    (systems::hud::stop_timer, systems::hud::tick_timer)
+                        .chain()
+                        .after(systems::uncover::trigger_event_handler)
+                        .after(systems::uncover::chord_event_handler),
+                    systems::stats::record_game_end::<T>
+                        .after(systems::hud::stop_timer)
+                        .run_if(resource_exists::<GameStats>)
+                        .run_if(not(resource_exists::<ReplayPlayer>)),
+                    (systems::replay::record_actions, systems::replay::save_replay::<T>)
+                        .chain()
+                        .run_if(resource_exists::<ReplayRecorder>)
+                        .run_if(not(resource_exists::<ReplayPlayer>)),
+                )
+                    .run_if(in_state(self.game_state.clone()))
+                    // No board is created from invalid options
+                    .run_if(resource_exists::<Board>))
+            .add_systems(
+                Last,
+                Self::save_on_exit
+                    .run_if(resource_exists::<BoardOptions<T>>)
+                    .run_if(not(resource_exists::<ReplayPlayer>)))
+            .add_systems(
+                Update,
+                (
+                    Self::resize_board,
+                    systems::hud::update_hud::<T>,
+                ).run_if(resource_exists::<Board>))
+            .add_systems(
+                Update,
+                (
+                    systems::input::history_input.run_if(not(resource_exists::<ReplayPlayer>)),
+                    systems::history::history_event_handler::<T>,
+                )
+                    .chain()
+                    .run_if(
+                        in_state(self.game_state.clone())
+                            .or_else(in_state(self.game_over_state.clone()))
+                            .or_else(in_state(self.won_state.clone())))
+                    .run_if(resource_exists::<Board>))
+            .add_systems(
+                Update,
+                // Recorded games go on after an undo of their end, keep playing in the end states
+                (systems::replay::play_replay, systems::replay::replay_controls)
+                    .before(Self::place_deferred_bombs)
+                    .before(systems::history::history_event_handler::<T>)
+                    .run_if(
+                        in_state(self.game_state.clone())
+                            .or_else(in_state(self.game_over_state.clone()))
+                            .or_else(in_state(self.won_state.clone())))
+                    .run_if(resource_exists::<ReplayPlayer>)
+                    .run_if(resource_exists::<Board>))
+            .add_systems(
+                Update,
+                systems::stats::load_stats::<T>
+                    .run_if(resource_exists::<BoardOptions<T>>)
+                    .run_if(not(resource_exists::<GameStats>)))
             .add_systems(
                 Update,
                 (
@@ -66,48 +170,220 @@ impl<T: FreelyMutableState> Plugin for BoardPlugin<T> {
                 (
                     Self::new_game
                 ).run_if(in_state(self.game_over_state.clone())))
+            .add_systems(
+                Update,
+                (
+                    Self::new_game
+                ).run_if(in_state(self.won_state.clone())))
+            .init_resource::<ProbabilityOverlay>()
+            .init_resource::<BoardCursor>()
+            .init_resource::<CameraDrag>()
             .add_event::<TileTriggerEvent>()
             .add_event::<TileMarkEvent>()
+            .add_event::<TileChordEvent>()
             .add_event::<BombExplosionEvent>()
-            .add_event::<BoardCompletedEvent>();
+            .add_event::<BoardCompletedEvent>()
+            .add_event::<HistoryEvent>()
+            .add_event::<BoardConfigErrorEvent>();
 
         info!("Loaded Board Plugin");
     }
 }
 
-impl<T: FreelyMutableState> BoardPlugin<T> {
-    //System to generate the complete board
+impl<T> BoardPlugin<T>
+where
+    T: FreelyMutableState + Serialize + DeserializeOwned,
+{
+    //System to generate the complete board, resuming the saved game if there is one
     pub fn create_board(
         mut commands: Commands,
-        board_options: Res<BoardOptions<T>>,
+        mut board_options: ResMut<BoardOptions<T>>,
         board_option: Option<Res<Board>>,
         board_assets: Res<BoardAssets>,
+        window_primary_query: Query<&Window, With<PrimaryWindow>>,
+        replay_player: Option<Res<ReplayPlayer>>,
+        mut board_config_error_event_writer: EventWriter<BoardConfigErrorEvent>,
     ) {
         if board_option.is_some() {
             return;
         }
 
-        let options = board_options.clone();
+        let window_size = Self::window_size(&window_primary_query);
+        if let Some(replay_player) = replay_player {
+            // Replays never resume the saved game
+            let replay = replay_player.replay();
+            let mut replay_options = board_options.clone();
+            replay.apply_options(&mut replay_options);
+            if let Err(error) = replay_options.validate() {
+                // Drop the replay, the player options stay in place for the next game
+                error!("Invalid replay board options: {}", error);
+                commands.remove_resource::<ReplayPlayer>();
+                board_config_error_event_writer.send(BoardConfigErrorEvent { error });
+                return;
+            }
+            commands.insert_resource(PlayerOptions(std::mem::replace(&mut *board_options, replay_options)));
+            info!("Playing replay of board with seed {}", replay.seed);
+            Self::new_board(&mut commands, &board_options, replay.seed, &board_assets, window_size);
+            return;
+        }
+        let snapshot = Self::load_snapshot(&board_options).map(|mut snapshot| {
+            // The rng source can't be saved, keep the configured one
+            snapshot.options.rng_source = board_options.rng_source;
+            // Stats are shared by all games, keep recording them to the configured file
+            snapshot.options.stats_path = board_options.stats_path.clone();
+            *board_options = snapshot.options.clone();
+            snapshot
+        });
+        if let Err(error) = board_options.validate() {
+            error!("Invalid board options: {}", error);
+            board_config_error_event_writer.send(BoardConfigErrorEvent { error });
+            return;
+        }
+        match snapshot {
+            Some(snapshot) => Self::load_board(&mut commands, snapshot, &board_assets, window_size),
+            None => {
+                let seed = board_options.next_seed();
+                Self::new_board(&mut commands, &board_options, seed, &board_assets, window_size)
+            }
+        }
+    }
 
-        let tile_size = options.tile_size_px();
+    /// System removing the board, its covers and the HUD, so the next game state entry creates a new one.
+    /// Meant for leaving the game to a menu
+    pub fn clear_board(
+        mut commands: Commands,
+        board_option: Option<Res<Board>>,
+        mut board_options: ResMut<BoardOptions<T>>,
+        player_options: Option<Res<PlayerOptions<T>>>,
+        cover_query: Query<Entity, CoverFilter>,
+        hud_query: Query<Entity, With<Hud>>,
+        camera_query: Query<(&mut Transform, &mut OrthographicProjection), With<Camera2d>>,
+    ) {
+        // The next board is shown centered whatever the camera was looking at
+        systems::camera::reset_camera(camera_query);
+        // A replay failing to start has no board, it still has to be left
+        Self::leave_replay(&mut commands, &mut board_options, player_options);
+        let Some(board) = board_option else { return };
+        commands.entity(board.entity).despawn_recursive();
+        for entity in cover_query.iter().chain(hud_query.iter()) {
+            commands.entity(entity).despawn_recursive();
+        }
+        commands.remove_resource::<Board>();
+        commands.remove_resource::<GameTimer>();
+    }
 
-        let mut tile_map = TileMap::empty(options.map_size.columns, options.map_size.rows);
+    /// Stops the replay, if any, and gives the player their options back
+    fn leave_replay(
+        commands: &mut Commands,
+        board_options: &mut BoardOptions<T>,
+        player_options: Option<Res<PlayerOptions<T>>>,
+    ) {
+        commands.remove_resource::<ReplayPlayer>();
+        let Some(player_options) = player_options else { return };
+        *board_options = player_options.0.clone();
+        commands.remove_resource::<PlayerOptions<T>>();
+    }
 
-        let board_size = options.board_size();
+    fn window_size(window_primary_query: &Query<&Window, With<PrimaryWindow>>) -> Vec2 {
+        window_primary_query
+            .get_single()
+            .map(|window| window.size())
+            .unwrap_or_default()
+    }
 
+    /// Generates a new tile map from `seed` and spawns its board, recording its replay
+    fn new_board(
+        commands: &mut Commands,
+        options: &BoardOptions<T>,
+        seed: u64,
+        board_assets: &BoardAssets,
+        window_size: Vec2,
+    ) {
+        info!("Generating board with seed {}", seed);
+        let bombs_placed = !options.first_click.is_deferred();
+        let tile_map = if bombs_placed {
+            options.generation.generate(
+                &options.empty_tile_map(),
+                options.bomb_count,
+                &mut options.rng(seed),
+                &[],
+            )
+        } else {
+            // Only covers are spawned, bombs are placed on the first reveal
+            options.empty_tile_map()
+        };
         #[cfg(feature = "debug")]
-        info!("board_size: {}", &board_size);
+        info!("info: {}", tile_map.console_output());
 
-        let board_position = options.board_position_px(BACKGROUND_Z);
+        let mut game = Game::new(tile_map);
+        if options.safe_start && bombs_placed {
+            if let Some(start) = game.tile_map().safe_start() {
+                game.reveal(start);
+            }
+        }
+
+        Self::spawn_board(
+            commands,
+            options,
+            game,
+            seed,
+            bombs_placed,
+            false,
+            Duration::ZERO,
+            board_assets,
+            window_size,
+        );
+        commands.insert_resource(ReplayRecorder::new(Replay::new(options, seed)));
+    }
+
+    /// Rebuilds the board of a saved game instead of generating a new tile map
+    fn load_board(
+        commands: &mut Commands,
+        snapshot: BoardSnapshot<T>,
+        board_assets: &BoardAssets,
+        window_size: Vec2,
+    ) {
+        info!("Resuming saved board with seed {}", snapshot.seed);
+        // The inputs before the save are unknown, no replay is recorded
+        commands.remove_resource::<ReplayRecorder>();
+        Self::spawn_board(
+            commands,
+            &snapshot.options,
+            snapshot.game,
+            snapshot.seed,
+            snapshot.bombs_placed,
+            snapshot.assisted,
+            snapshot.elapsed,
+            board_assets,
+            window_size,
+        );
+    }
+
+    /// Spawns the board entities of `game` and inserts the `Board` resource
+    #[allow(clippy::too_many_arguments)]
+    fn spawn_board(
+        commands: &mut Commands,
+        options: &BoardOptions<T>,
+        game: Game,
+        seed: u64,
+        bombs_placed: bool,
+        assisted: bool,
+        elapsed: Duration,
+        board_assets: &BoardAssets,
+        window_size: Vec2,
+    ) {
+        let tile_size = options.tile_size_px(window_size);
+
+        let board_size = options.board_size(window_size);
 
-        tile_map.set_bombs(options.bomb_count);
         #[cfg(feature = "debug")]
-        info!("info: {}", tile_map.console_output());
+        info!("board_size: {}", &board_size);
 
-        let mut covered_tiles =
-            HashMap::with_capacity((tile_map.width() * tile_map.height()).into());
+        let board_position = options.board_position_px(BACKGROUND_Z, window_size);
 
-        let mut safe_start: Option<Entity> = None;
+        let tile_count = game.tile_map().tile_count();
+        let mut tiles = HashMap::with_capacity(tile_count);
+        let mut covered_tiles = HashMap::with_capacity(tile_count);
 
         let board_entity = commands
             .spawn((
@@ -118,71 +394,153 @@ impl<T: FreelyMutableState> BoardPlugin<T> {
                 },
             ))
             .with_children(|parent| {
-                parent
-                    .spawn(SpriteBundle {
-                        sprite: Sprite {
-                            color: board_assets.board_material.color,
-                            custom_size: Some(board_size),
-                            ..Default::default()
-                        },
-                        texture: board_assets.board_material.texture.clone(),
-                        transform: Transform::from_xyz(
-                            board_size.x / 2.0,
-                            board_size.y / 2.0,
-                            BACKGROUND_Z,
-                        ),
-                        ..Default::default()
-                    })
-                    .insert(Name::new("Background"));
-
+                Self::spawn_background(parent, game.tile_map(), tile_size, board_size, board_assets);
                 Self::spawn_tiles(
                     parent,
-                    &tile_map,
+                    &game,
                     tile_size,
                     options.tile_padding,
-                    &board_assets,
+                    board_assets,
+                    &mut tiles,
                     &mut covered_tiles,
-                    &mut safe_start,
                 );
+                if game.tile_map().is_wrapping() {
+                    Self::spawn_wrap_edges(parent, game.tile_map(), tile_size, board_size);
+                }
             })
             .id();
 
-        if options.safe_start {
-            if let Some(entity) = safe_start {
-                commands.entity(entity).insert(Uncover);
+        for coordinates in game.flagged() {
+            if let Some(entity) = covered_tiles.get(coordinates) {
+                let count = game.flag_count_at(coordinates);
+                systems::mark::spawn_flag(commands, *entity, board_assets, tile_size, count);
+            }
+        }
+        for coordinates in game.questioned() {
+            if let Some(entity) = covered_tiles.get(coordinates) {
+                systems::mark::spawn_question_mark(commands, *entity, board_assets, tile_size);
             }
         }
 
         commands.insert_resource(Board {
-            tile_map: tile_map.clone(),
+            game,
             bounds: Bounds2 {
                 position: board_position.xy(),
                 size: board_size,
             },
             tile_size,
+            layout_tile_size: tile_size,
+            tile_padding: options.tile_padding,
+            tiles,
             covered_tiles,
-            marked_tiles: Vec::new(),
             entity: board_entity,
+            seed,
+            bombs_placed,
+            history: History::default(),
+            assisted,
+            revision: 0,
+            recorded_stats: None,
         });
+        commands.insert_resource(GameTimer::resumed(elapsed));
     }
-    fn spawn_tiles(
+
+    /// Saved game to resume, if any
+    fn load_snapshot(options: &BoardOptions<T>) -> Option<BoardSnapshot<T>> {
+        let path = options.save_path.as_ref()?;
+        if !path.exists() {
+            return None;
+        }
+        match BoardSnapshot::<T>::load(path) {
+            Ok(snapshot) if snapshot.game.status() == GameStatus::InProgress => Some(snapshot),
+            Ok(_) => None,
+            Err(e) => {
+                error!("Failed to load saved game from {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// Saves the board if the game is still in progress, removes the save otherwise
+    fn save_board(board: &Board, options: &BoardOptions<T>, game_timer: &GameTimer) {
+        let Some(path) = options.save_path.as_ref() else { return };
+        if board.game.status() != GameStatus::InProgress {
+            Self::remove_save(options);
+            return;
+        }
+        match BoardSnapshot::new(board, options, game_timer).save(path) {
+            Ok(()) => info!("Game saved to {}", path.display()),
+            Err(e) => error!("Failed to save game to {}: {}", path.display(), e),
+        }
+    }
+
+    /// Removes the saved game, which can't be resumed once it ended
+    pub fn remove_save(options: &BoardOptions<T>) {
+        let Some(path) = options.save_path.as_ref() else { return };
+        if path.exists() {
+            if let Err(e) = std::fs::remove_file(path) {
+                error!("Failed to remove saved game {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    /// Spawns the board background, tile by tile on masked boards to show their shape
+    fn spawn_background(
         parent: &mut ChildBuilder,
         tile_map: &TileMap,
         tile_size: f32,
+        board_size: Vec2,
+        board_assets: &BoardAssets,
+    ) {
+        let topology = tile_map.topology();
+        let rectangle_tile_count = tile_map.width() as usize * tile_map.height() as usize;
+        let (backgrounds, texture): (Vec<(Vec2, Vec2)>, _) = if tile_map.tile_count() == rectangle_tile_count {
+            (vec![(board_size / 2.0, board_size)], board_assets.board_material.texture.clone())
+        } else {
+            let size = layout::sprite_size(topology, tile_size);
+            let backgrounds = tile_map
+                .coordinates()
+                .map(|coordinates| (layout::tile_center(topology, coordinates, tile_size), size))
+                .collect();
+            (backgrounds, board_assets.tile_texture(topology, &board_assets.board_material))
+        };
+        for (position, size) in backgrounds {
+            parent
+                .spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color: board_assets.board_material.color,
+                        custom_size: Some(size),
+                        ..Default::default()
+                    },
+                    texture: texture.clone(),
+                    transform: Transform::from_translation(position.extend(BACKGROUND_Z)),
+                    ..Default::default()
+                })
+                .insert(Name::new("Background"));
+        }
+    }
+
+    fn spawn_tiles(
+        parent: &mut ChildBuilder,
+        game: &Game,
+        tile_size: f32,
         tile_padding: f32,
         board_assets: &BoardAssets,
+        tiles: &mut HashMap<Coordinates, Entity>,
         covered_tiles: &mut HashMap<Coordinates, Entity>,
-        safe_start_entity: &mut Option<Entity>,
     ) {
+        let topology = game.tile_map().topology();
         let tile_real_size = tile_size - tile_padding;
-        let sprites_size = Some(Vec2::splat(tile_real_size));
+        let sprites_size = layout::sprite_size(topology, tile_real_size);
 
-        for (y, line) in tile_map.iter().enumerate() {
+        for (y, line) in game.tile_map().iter().enumerate() {
             for (x, tile) in line.iter().enumerate() {
                 let coordinates = Coordinates {
                     coord_x: x as u16,
                     coord_y: y as u16,
                 };
+                if game.tile_map().is_hole(coordinates) {
+                    continue;
+                }
 
                 #[cfg(feature = "debug")]
                 info!("Spawn tile {:?} at {:?}", tile, coordinates);
@@ -190,14 +548,12 @@ impl<T: FreelyMutableState> BoardPlugin<T> {
                 let mut commands = parent.spawn(SpriteBundle {
                     sprite: Sprite {
                         color: board_assets.tile_material.color,
-                        custom_size: Some(Vec2::splat(tile_size - tile_padding)),
+                        custom_size: Some(sprites_size),
                         ..Default::default()
                     },
-                    texture: board_assets.tile_material.texture.clone(),
-                    transform: Transform::from_xyz(
-                        (x as f32 * tile_size) + (tile_size / 2.0),
-                        (y as f32 * tile_size) + (tile_size / 2.0),
-                        TILE_Z,
+                    texture: board_assets.tile_texture(topology, &board_assets.tile_material),
+                    transform: Transform::from_translation(
+                        layout::tile_center(topology, coordinates, tile_size).extend(TILE_Z),
                     ),
                     ..Default::default()
                 });
@@ -205,54 +561,155 @@ impl<T: FreelyMutableState> BoardPlugin<T> {
                 commands
                     .insert(Name::new(format!("Tile: ({}, {})", x, y)))
                     .insert(coordinates);
+                tiles.insert(coordinates, commands.id());
+
+                if game.is_covered(&coordinates) {
+                    commands.with_children(|parent| {
+                        let entity = systems::uncover::spawn_cover(parent, board_assets, topology, sprites_size);
+                        covered_tiles.insert(coordinates, entity);
+                    });
+                }
+
+                Self::spawn_tile_content(&mut commands, tile, board_assets, tile_real_size);
+            }
+        }
+    }
 
+    /// Dashes facing each other on opposite board edges, at every row and column,
+    /// showing that the board wraps around
+    fn spawn_wrap_edges(parent: &mut ChildBuilder, tile_map: &TileMap, tile_size: f32, board_size: Vec2) {
+        let color = Color::from(tailwind::SKY_400);
+        let length = tile_size * 0.6;
+        let thickness = tile_size * 0.12;
+        let topology = tile_map.topology();
+        let center = |coord_x, coord_y| layout::tile_center(topology, Coordinates { coord_x, coord_y }, tile_size);
+        let top_row = tile_map.height() - 1;
+        let right_column = tile_map.width() - 1;
+        let horizontal = Vec2::new(length, thickness);
+        let vertical = Vec2::new(thickness, length);
+        // Each marker sits by the edge tile it wraps from
+        let columns = (0..tile_map.width()).flat_map(|coord_x| {
+            [
+                (coord_x, 0, Vec2::new(center(coord_x, 0).x, -thickness), horizontal),
+                (coord_x, top_row, Vec2::new(center(coord_x, top_row).x, board_size.y + thickness), horizontal),
+            ]
+        });
+        let rows = (0..tile_map.height()).flat_map(|coord_y| {
+            let y = center(0, coord_y).y;
+            [
+                (0, coord_y, Vec2::new(-thickness, y), vertical),
+                (right_column, coord_y, Vec2::new(board_size.x + thickness, y), vertical),
+            ]
+        });
+        // Masked boards have no tile to wrap from on their hole edges
+        let markers = columns
+            .chain(rows)
+            .filter(|&(coord_x, coord_y, ..)| !tile_map.is_hole(Coordinates { coord_x, coord_y }))
+            .map(|(_, _, position, size)| (position, size));
+        for (position, size) in markers {
+            parent
+                .spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color,
+                        custom_size: Some(size),
+                        ..Default::default()
+                    },
+                    transform: Transform::from_translation(position.extend(BACKGROUND_Z)),
+                    ..Default::default()
+                })
+                .insert(Name::new("Wrap edge"));
+        }
+    }
+
+    /// Inserts the bomb components and spawns the bomb or bomb counter sprite of a tile
+    fn spawn_tile_content(
+        commands: &mut EntityCommands,
+        tile: &Tile,
+        board_assets: &BoardAssets,
+        tile_real_size: f32,
+    ) {
+        match tile {
+            Tile::Bomb(count) => {
+                commands.insert(components::Bomb { count: *count });
                 commands.with_children(|parent| {
-                    let entity = parent
-                        .spawn(SpriteBundle {
-                            sprite: Sprite {
-                                custom_size: sprites_size,
-                                color: board_assets.covered_tile_material.color,
-                                ..Default::default()
-                            },
-                            texture: board_assets.covered_tile_material.texture.clone(),
-                            transform: Transform::from_xyz(0.0, 0.0, TILE_COVER_Z),
+                    parent.spawn(SpriteBundle {
+                        sprite: Sprite {
+                            custom_size: Some(Vec2::splat(tile_real_size)),
                             ..Default::default()
-                        })
-                        .insert(Name::new("Tile Cover"))
-                        .id();
-                    covered_tiles.insert(coordinates, entity);
-                    if safe_start_entity.is_none() && *tile == Tile::Empty {
-                        *safe_start_entity = Some(entity);
+                        },
+                        transform: Transform::from_xyz(0.0, 0.0, TILE_INFO_Z),
+                        texture: board_assets.bomb_material.texture.clone(),
+                        ..Default::default()
+                    });
+                    if *count > 1 {
+                        parent.spawn(systems::mark::quantity_text_bundle(
+                            *count,
+                            board_assets,
+                            tile_real_size,
+                        ));
                     }
                 });
+            }
+            Tile::BombNeighbor(bombs_count) => {
+                commands.insert(components::BombNeighbor { count: *bombs_count });
+                commands.with_children(|parent| {
+                    parent.spawn(Self::bomb_count_text_bundle(
+                        *bombs_count,
+                        board_assets,
+                        tile_real_size,
+                    ));
+                });
+            }
+            Tile::Empty => (),
+        }
+    }
 
-                match tile {
-                    Tile::Bomb => {
-                        commands.insert(components::Bomb);
-                        commands.with_children(|parent| {
-                            parent.spawn(SpriteBundle {
-                                sprite: Sprite {
-                                    custom_size: sprites_size,
-                                    ..Default::default()
-                                },
-                                transform: Transform::from_xyz(0.0, 0.0, TILE_INFO_Z),
-                                texture: board_assets.bomb_material.texture.clone(),
-                                ..Default::default()
-                            });
-                        });
-                    }
-                    Tile::BombNeighbor(bombs_count) => {
-                        commands.insert(components::BombNeighbor { count: *bombs_count });
-                        commands.with_children(|parent| {
-                            parent.spawn(Self::bomb_count_text_bundle(
-                                *bombs_count,
-                                &board_assets,
-                                tile_size - tile_padding,
-                            ));
-                        });
-                    }
-                    Tile::Empty => (),
-                }
+    /// Places the bombs around the first revealed tile when bomb placement is deferred
+    fn place_deferred_bombs(
+        mut commands: Commands,
+        mut board: ResMut<Board>,
+        board_options: Res<BoardOptions<T>>,
+        board_assets: Res<BoardAssets>,
+        mut tile_trigger_event_reader: EventReader<TileTriggerEvent>,
+        tiles: Query<(Entity, &Coordinates)>,
+    ) {
+        if board.bombs_placed {
+            tile_trigger_event_reader.clear();
+            return;
+        }
+        let Some(coordinates) = tile_trigger_event_reader
+            .read()
+            .map(|event| event.coordinates)
+            .find(|coordinates| {
+                board.game.is_covered(coordinates) && !board.game.is_flagged(coordinates)
+            })
+        else {
+            return;
+        };
+
+        let options = board_options.as_ref();
+        let safe_area = options.first_click.safe_area(board.game.tile_map(), coordinates);
+        let seed = board.seed;
+        let tile_map = options.generation.generate(
+            &options.empty_tile_map(),
+            options.bomb_count,
+            &mut options.rng(seed),
+            &safe_area,
+        );
+        #[cfg(feature = "debug")]
+        info!("info: {}", tile_map.console_output());
+        board.game.replace_tile_map(tile_map);
+        board.bombs_placed = true;
+
+        let tile_real_size = board.layout_tile_size - options.tile_padding;
+        for (entity, coordinates) in tiles.iter() {
+            if let Some(tile) = board.game.tile_map().tile(*coordinates) {
+                Self::spawn_tile_content(
+                    &mut commands.entity(entity),
+                    &tile,
+                    &board_assets,
+                    tile_real_size,
+                );
             }
         }
     }
@@ -277,40 +734,50 @@ impl<T: FreelyMutableState> BoardPlugin<T> {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn recreate_board(
         mut commands: Commands,
         keys: Res<ButtonInput<KeyCode>>,
         board: Res<Board>,
-        board_options: Res<BoardOptions<T>>,
+        mut board_options: ResMut<BoardOptions<T>>,
+        player_options: Option<Res<PlayerOptions<T>>>,
         board_assets: Res<BoardAssets>,
+        window_primary_query: Query<&Window, With<PrimaryWindow>>,
     ) {
         if keys.just_released(KeyCode::KeyG) {
             info!("G is released");
             commands.entity(board.entity).despawn_recursive();
-            BoardPlugin::create_board(commands, board_options, None, board_assets)
+            // Leaving a replay, the new board is played with the player options
+            Self::leave_replay(&mut commands, &mut board_options, player_options);
+            let window_size = Self::window_size(&window_primary_query);
+            let seed = board_options.next_seed();
+            Self::new_board(&mut commands, &board_options, seed, &board_assets, window_size);
         }
     }
 
     fn pause(
         mut commands: Commands,
-        keys: Res<ButtonInput<KeyCode>>,
+        pause_input: PauseInput,
         mut next_state: ResMut<NextState<T>>,
+        board: Res<Board>,
         board_options: Res<BoardOptions<T>>,
         board_assets: Res<BoardAssets>,
+        game_timer: Res<GameTimer>,
     ) {
-        if keys.just_released(KeyCode::KeyP) {
+        if pause_input.just_released() {
             next_state.set(board_options.pause_state.clone());
+            Self::save_board(&board, &board_options, &game_timer);
 
             let font: Handle<Font> = board_assets.menu_font.clone();
             let text_style = TextStyle {
                 font,
-                font_size: board_options.tile_size_px(),
+                font_size: board.tile_size,
                 color: Color::from(tailwind::YELLOW_200),
             };
-            let text = Text::from_section("Paused! Press P to continue", text_style)
+            let text = Text::from_section("Paused! Press P or Start to continue", text_style)
                 .with_justify(JustifyText::Center);
 
-            let board_size = board_options.board_size();
+            let board_size = board.bounds.size;
             commands
                 .spawn(SpriteBundle {
                     sprite: Sprite {
@@ -335,34 +802,41 @@ impl<T: FreelyMutableState> BoardPlugin<T> {
 
     fn unpause(
         mut commands: Commands,
-        keys: Res<ButtonInput<KeyCode>>,
+        pause_input: PauseInput,
         mut next_state: ResMut<NextState<T>>,
         board_options: Res<BoardOptions<T>>,
         pause_cover_query: Query<Entity, With<PauseCover>>,
     ) {
-        if keys.just_released(KeyCode::KeyP) {
+        if pause_input.just_released() {
             let x: Entity = pause_cover_query.single();
             commands.entity(x).despawn_recursive();
             next_state.set(board_options.game_state.clone())
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn new_game (
         mut commands: Commands,
         mut next_state: ResMut<NextState<T>>,
         keys: Res<ButtonInput<KeyCode>>,
         board: Res<Board>,
-        board_options: Res<BoardOptions<T>>,
+        mut board_options: ResMut<BoardOptions<T>>,
+        player_options: Option<Res<PlayerOptions<T>>>,
         board_assets: Res<BoardAssets>,
-        game_over_cover_query: Query<Entity, With<GameOverCover>>,
+        end_cover_query: Query<Entity, EndCoverFilter>,
+        window_primary_query: Query<&Window, With<PrimaryWindow>>,
     ) {
         if keys.just_released(KeyCode::KeyN) {
             info!("Starting a new game");
             next_state.set(board_options.game_state.clone());
-            let x: Entity = game_over_cover_query.single();
+            let x: Entity = end_cover_query.single();
             commands.entity(x).despawn_recursive();
             commands.entity(board.entity).despawn_recursive();
-            BoardPlugin::create_board(commands, board_options, None, board_assets)
+            // Leaving a replay, the new board is played with the player options
+            Self::leave_replay(&mut commands, &mut board_options, player_options);
+            let window_size = Self::window_size(&window_primary_query);
+            let seed = board_options.next_seed();
+            Self::new_board(&mut commands, &board_options, seed, &board_assets, window_size);
         }
     }
 
@@ -370,22 +844,28 @@ impl<T: FreelyMutableState> BoardPlugin<T> {
         mut commands: Commands,
         mut bomb_explosion_event: EventReader<BombExplosionEvent>,
         mut next_state: ResMut<NextState<T>>,
+        board: Res<Board>,
         board_options: Res<BoardOptions<T>>,
         board_assets: Res<BoardAssets>,
+        replay_player: Option<Res<ReplayPlayer>>,
     ) {
-        for event in bomb_explosion_event.read() {
+        for _event in bomb_explosion_event.read() {
             next_state.set(board_options.game_over_state.clone());
+            // A replay doesn't end the saved game
+            if replay_player.is_none() {
+                Self::remove_save(&board_options);
+            }
 
             let font: Handle<Font> = board_assets.menu_font.clone();
             let text_style = TextStyle {
                 font,
-                font_size: board_options.tile_size_px(),
+                font_size: board.tile_size,
                 color: Color::from(tailwind::RED_900),
             };
             let text = Text::from_section("Game Over! Press N for new game", text_style)
                 .with_justify(JustifyText::Center);
 
-            let board_size = board_options.board_size();
+            let board_size = board.bounds.size;
             commands
                 .spawn(SpriteBundle {
                     sprite: Sprite {
@@ -408,6 +888,107 @@ impl<T: FreelyMutableState> BoardPlugin<T> {
                 });
         }
     }
+    fn victory(
+        mut commands: Commands,
+        mut board_completed_event: EventReader<BoardCompletedEvent>,
+        mut next_state: ResMut<NextState<T>>,
+        board: Res<Board>,
+        board_options: Res<BoardOptions<T>>,
+        board_assets: Res<BoardAssets>,
+        replay_player: Option<Res<ReplayPlayer>>,
+    ) {
+        if board_completed_event.is_empty() {
+            return;
+        }
+        board_completed_event.clear();
+        next_state.set(board_options.won_state.clone());
+        // A replay doesn't end the saved game
+        if replay_player.is_none() {
+            Self::remove_save(&board_options);
+        }
+
+        let font: Handle<Font> = board_assets.menu_font.clone();
+        let text_style = TextStyle {
+            font,
+            font_size: board.tile_size,
+            color: Color::from(tailwind::YELLOW_200),
+        };
+        let text = Text::from_section("You won! Press N for new game", text_style)
+            .with_justify(JustifyText::Center);
+
+        let board_size = board.bounds.size;
+        commands
+            .spawn(SpriteBundle {
+                sprite: Sprite {
+                    // See-through so the flagged bombs stay visible
+                    color: Color::from(tailwind::EMERALD_800).with_alpha(0.6),
+                    custom_size: Some(board_size),
+                    ..Default::default()
+                },
+                transform: Transform::from_xyz(0.0, 0.0, PAUSE_COVER_Z),
+                ..Default::default()
+            })
+            .insert(Name::new("Victory cover"))
+            .insert(VictoryCover)
+            .with_children(|parent| {
+                parent.spawn(Text2dBundle {
+                    text,
+                    transform: Transform::from_xyz(0.0, 0.0, PAUSE_COVER_Z + 1.0),
+                    ..Default::default()
+                });
+            });
+    }
+
+    /// Saves the game in progress when the app exits
+    fn save_on_exit(
+        mut app_exit_event_reader: EventReader<AppExit>,
+        board: Option<Res<Board>>,
+        board_options: Res<BoardOptions<T>>,
+        game_timer: Option<Res<GameTimer>>,
+    ) {
+        if app_exit_event_reader.is_empty() {
+            return;
+        }
+        app_exit_event_reader.clear();
+        if let (Some(board), Some(game_timer)) = (board, game_timer) {
+            Self::save_board(&board, &board_options, &game_timer);
+        }
+    }
+
+    /// Fits an adaptive board to the resized window by moving and scaling the board entity
+    fn resize_board(
+        mut window_resized_event_reader: EventReader<WindowResized>,
+        window_primary_query: Query<&Window, With<PrimaryWindow>>,
+        mut board: ResMut<Board>,
+        board_options: Res<BoardOptions<T>>,
+        mut transforms: Query<&mut Transform>,
+        mut covers: Query<&mut Sprite, CoverFilter>,
+    ) {
+        if window_resized_event_reader.is_empty() {
+            return;
+        }
+        window_resized_event_reader.clear();
+        let Ok(window) = window_primary_query.get_single() else { return };
+
+        let window_size = window.size();
+        let board_size = board_options.board_size(window_size);
+        let board_position = board_options.board_position_px(BACKGROUND_Z, window_size);
+        board.tile_size = board_options.tile_size_px(window_size);
+        board.bounds = Bounds2 {
+            position: board_position.xy(),
+            size: board_size,
+        };
+
+        if let Ok(mut transform) = transforms.get_mut(board.entity) {
+            let scale = board.tile_size / board.layout_tile_size;
+            transform.translation = board_position;
+            transform.scale = Vec3::new(scale, scale, 1.0);
+        }
+        for mut sprite in covers.iter_mut() {
+            sprite.custom_size = Some(board_size);
+        }
+    }
+
     fn on_exit_log() {
         info!("exit from state")
     }
@@ -1,96 +1,94 @@
 use std::collections::HashMap;
-use bevy::prelude::{error, Entity, Resource, Vec2, Window};
+use bevy::prelude::{Entity, Resource, Vec2};
+use minesweeper_core::BoardTopology;
+use minesweeper_core::{Game, History};
 use crate::bounds::Bounds2;
 use crate::components::Coordinates;
-use crate::resources::tile_map::TileMap;
+use crate::layout;
+use crate::resources::ConfigurationStats;
 
 #[derive(Resource)]
 pub struct Board {
-    pub tile_map: TileMap,
+    pub game: Game,
     pub bounds: Bounds2,
     pub tile_size: f32,
+    /// Tile size the tiles were spawned with, the board entity is scaled to match `tile_size`
+    pub layout_tile_size: f32,
+    /// Space between the tiles
+    pub tile_padding: f32,
+    /// Tile entities
+    pub tiles: HashMap<Coordinates, Entity>,
+    /// Cover entities of the covered tiles
     pub covered_tiles: HashMap<Coordinates, Entity>,
     pub entity: Entity,
-    pub marked_tiles: Vec<Coordinates>,
+    /// Seed the tile map was generated from
+    pub seed: u64,
+    /// Are the bombs placed? False until the first reveal when bomb placement is deferred
+    pub bombs_placed: bool,
+    /// Played actions, for undo and redo
+    pub history: History,
+    /// Was an action undone or a hint shown? Assisted games are recorded apart in the stats
+    pub assisted: bool,
+    /// Bumped by every applied game change: reveals, marks, chords, undo and redo
+    pub revision: u64,
+    /// Stats of the board configuration before the end of this game was recorded,
+    /// restored when the end is undone
+    pub recorded_stats: Option<ConfigurationStats>,
 }
 
 impl Board {
-    //Translate mouse position to board coordinates
-    pub fn mouse_position(&self, window: &Window, position: Vec2) -> Option<Coordinates> {
-        //Window to world space
-        let window_size = Vec2 {
-            x: window.width(),
-            y: window.height(),
-        };
-        let position_at_board = position - (window_size / 2.0);
+    pub fn topology(&self) -> BoardTopology {
+        self.game.tile_map().topology()
+    }
 
+    /// Translates a world position, like the mouse cursor seen through the camera, to board coordinates
+    pub fn tile_at(&self, world_position: Vec2) -> Option<Coordinates> {
         // Bounds check
-        if !self.bounds.in_bounds(position_at_board) {
+        if !self.bounds.in_bounds(world_position) {
             return None;
         }
 
         // World space to board space
-        let coordinates = position_at_board - self.bounds.position;
-        Some(Coordinates {
-            coord_x: (coordinates.x / self.tile_size) as u16,
-            coord_y: self.tile_map.height() - 1 - (coordinates.y / self.tile_size) as u16,
-        })
+        let tile_map = self.game.tile_map();
+        layout::tile_at(
+            self.topology(),
+            world_position - self.bounds.position,
+            self.tile_size,
+            tile_map.width(),
+            tile_map.height(),
+        )
     }
 
-    /// Retrivies a covered tile entity
-    pub fn tile_to_uncover(&self, coordinates: &Coordinates) -> Option<&Entity> {
-        if self.marked_tiles.contains(coordinates) {
-            None
-        } else {
-            self.covered_tiles.get(coordinates)
-        }
+    /// Center of a tile in the board entity space, laid out at the layout tile size
+    pub fn tile_translation(&self, coordinates: Coordinates) -> Vec2 {
+        layout::tile_center(self.topology(), coordinates, self.layout_tile_size)
     }
 
-    /// We try to uncover a tile, returning the entity
-    pub fn try_uncover_tile(&mut self, coordinates: &Coordinates) -> Option<Entity> {
-        if self.marked_tiles.contains(coordinates) {
-            self.unmark_tile(coordinates);
-        }
-        self.covered_tiles.remove(coordinates)
+    /// Size of the tile and cover sprites, in the board entity space
+    pub fn tile_sprite_size(&self) -> Vec2 {
+        layout::sprite_size(self.topology(), self.layout_tile_size - self.tile_padding)
     }
+}
 
-    /// We retrieve the adjacent covered tile entities of `coordinates`
-    pub fn adjacent_covered_tiles(&self, coordinates: Coordinates) -> Vec<Entity> {
-        self
-            .tile_map
-            .safe_square_at(coordinates)
-            .filter_map(|c| self.covered_tiles.get(&c))
-            .copied()
-            .collect()
-    }
-    
-    //// Try to mark or unmark a tile, returning the entity and if the tile is marked
-    pub fn try_toggle_mark(&mut self, coordinates: &Coordinates) -> Option<(Entity, bool)> {
-        let entity = *self.covered_tiles.get(coordinates)?;
-        let mark = if self.marked_tiles.contains(coordinates) {
-            self.unmark_tile(coordinates)?;
-            false
-        } else {
-            self.marked_tiles.push(*coordinates);
-            true
-        };
-        Some((entity, mark))
-    }
-    
-    ///Remove the coords from marked_tiles
-    fn unmark_tile(&mut self, coordinates: &Coordinates) -> Option<Coordinates> {
-        let pos = match self.marked_tiles.iter().position(|a| a == coordinates) {
-            None => {
-                error!("Failed to unmark tile at {}", coordinates);
-                return None;
-            }
-            Some(p) => p,
-        };
-        Some(self.marked_tiles.remove(pos))
-    }
-    
-    ///Check if board is completed
-    pub fn is_completed(&self) -> bool {
-        self.tile_map.bomb_count() as usize == self.covered_tiles.len()
+#[cfg(test)]
+impl Board {
+    /// Board of `game` without any spawned entity
+    pub(crate) fn detached(game: Game) -> Self {
+        Self {
+            game,
+            bounds: Bounds2 { position: Vec2::ZERO, size: Vec2::ZERO },
+            tile_size: 10.0,
+            layout_tile_size: 10.0,
+            tile_padding: 0.0,
+            tiles: HashMap::new(),
+            covered_tiles: HashMap::new(),
+            entity: Entity::PLACEHOLDER,
+            seed: 0,
+            bombs_placed: true,
+            history: History::default(),
+            assisted: false,
+            revision: 0,
+            recorded_stats: None,
+        }
     }
-}
\ No newline at end of file
+}
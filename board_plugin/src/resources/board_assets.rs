@@ -1,5 +1,6 @@
 use bevy::color::palettes::tailwind;
 use bevy::prelude::{Color, Font, Handle, Image, Resource};
+use minesweeper_core::BoardTopology;
 // use bevy::render::*;
 
 #[derive(Debug, Clone, Default)]
@@ -34,9 +35,12 @@ pub struct BoardAssets {
     pub board_material: SpriteMaterial,
     pub tile_material: SpriteMaterial,
     pub covered_tile_material: SpriteMaterial,
+    /// White hexagon tinted by the tile materials on hex boards
+    pub hex_tile_texture: Handle<Image>,
     pub bomb_counter_font: Handle<Font>,
     pub bomb_counter_colors: Vec<Color>,
     pub flag_material: SpriteMaterial,
+    pub question_material: SpriteMaterial,
     pub bomb_material: SpriteMaterial,
     pub menu_font: Handle<Font>,
 }
@@ -54,6 +58,19 @@ impl BoardAssets {
         ]
     }
 
+    /// Texture of a tile or cover drawn with `material`, hexagons replace it on hex boards
+    pub fn tile_texture(&self, topology: BoardTopology, material: &SpriteMaterial) -> Handle<Image> {
+        match topology {
+            BoardTopology::Square => material.texture.clone(),
+            BoardTopology::Hex => self.hex_tile_texture.clone(),
+        }
+    }
+
+    /// Untextured sprites shaped like the tiles, for overlays
+    pub fn overlay_texture(&self, topology: BoardTopology) -> Handle<Image> {
+        self.tile_texture(topology, &SpriteMaterial::default())
+    }
+
     //Safely retrieves the color matching a bomb counter
     pub fn bomb_counter_color(&self, counter: u8) -> Color {
         let counter = counter.saturating_sub(1) as usize;
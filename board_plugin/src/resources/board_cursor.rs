@@ -0,0 +1,78 @@
+use bevy::prelude::Resource;
+use crate::components::Coordinates;
+use crate::resources::tile_map::TileMap;
+
+/// Keyboard selected tile, shown once moved with the keyboard
+#[derive(Debug, Clone, Default, Resource)]
+pub struct BoardCursor {
+    pub coordinates: Coordinates,
+    pub visible: bool,
+}
+
+impl BoardCursor {
+    /// Moves by `(x, y)` tiles, staying on `tile_map` or wrapping around it, and shows the cursor.
+    /// Holes are skipped in the direction of travel, the cursor stays put when only holes are left
+    pub fn move_by(&mut self, delta: (i32, i32), tile_map: &TileMap) {
+        self.visible = true;
+        let mut coordinates = self.coordinates;
+        loop {
+            let next = Self::step(coordinates, delta, tile_map);
+            // Stopped by an edge, or back to the start around a wrapping map
+            if next == coordinates || next == self.coordinates {
+                return;
+            }
+            if !tile_map.is_hole(next) {
+                self.coordinates = next;
+                return;
+            }
+            coordinates = next;
+        }
+    }
+
+    fn step(coordinates: Coordinates, (x, y): (i32, i32), tile_map: &TileMap) -> Coordinates {
+        if tile_map.is_wrapping() {
+            return coordinates.wrapping_add((x as i8, y as i8), tile_map.width(), tile_map.height());
+        }
+        Coordinates {
+            coord_x: (coordinates.coord_x as i32 + x).clamp(0, tile_map.width() as i32 - 1) as u16,
+            coord_y: (coordinates.coord_y as i32 + y).clamp(0, tile_map.height() as i32 - 1) as u16,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::BoardMask;
+
+    fn masked_map(description: &str, wrapping: bool) -> TileMap {
+        let mask: BoardMask = description.parse().unwrap();
+        let mut tile_map = TileMap::empty(mask.width(), mask.height());
+        tile_map.set_mask(&mask);
+        tile_map.set_wrapping(wrapping);
+        tile_map
+    }
+
+    #[test]
+    fn moves_skip_the_holes() {
+        let tile_map = masked_map("#..#\n####", false);
+        let mut board_cursor = BoardCursor { coordinates: Coordinates { coord_x: 0, coord_y: 1 }, visible: false };
+        board_cursor.move_by((1, 0), &tile_map);
+        assert!(board_cursor.visible);
+        assert_eq!(board_cursor.coordinates, Coordinates { coord_x: 3, coord_y: 1 });
+    }
+
+    #[test]
+    fn moves_towards_holes_only_stay_put() {
+        let tile_map = masked_map("#..\n###", false);
+        let mut board_cursor = BoardCursor { coordinates: Coordinates { coord_x: 0, coord_y: 1 }, visible: true };
+        board_cursor.move_by((1, 0), &tile_map);
+        assert_eq!(board_cursor.coordinates, Coordinates { coord_x: 0, coord_y: 1 });
+
+        let tile_map = masked_map("#..\n###", true);
+        board_cursor.move_by((1, 0), &tile_map);
+        assert_eq!(board_cursor.coordinates, Coordinates { coord_x: 0, coord_y: 1 });
+        board_cursor.move_by((-1, 0), &tile_map);
+        assert_eq!(board_cursor.coordinates, Coordinates { coord_x: 0, coord_y: 1 });
+    }
+}
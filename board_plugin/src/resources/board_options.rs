@@ -1,10 +1,22 @@
 use bevy::{prelude::Vec3, ecs::system::Resource};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
 use bevy::math::Vec2;
 use bevy::state::state::FreelyMutableState;
+use rand::rngs::StdRng;
+use rand::{thread_rng, Rng, RngCore, SeedableRng};
 use serde::{Deserialize, Serialize};
+use crate::components::Coordinates;
+use crate::layout;
+use crate::resources::{BoardGeneration, BoardMask, BoardTopology, Neighborhood};
+use crate::resources::tile_map::TileMap;
+
+/// Builds the random number generator used to place bombs from a board seed
+pub type RngSource = fn(u64) -> Box<dyn RngCore>;
 
 //Tile size options
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum TileSize {
     // Fixed tile size
     Fixed(f32),
@@ -21,12 +33,92 @@ pub enum BoardPosition {
     Custom(Vec3),
 }
 
+//First click protection options
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FirstClick {
+    // Bombs are placed when the board is created
+    #[default]
+    Unprotected,
+    // Bombs are placed after the first reveal, never on the revealed tile
+    SafeTile,
+    // Bombs are placed after the first reveal, never on the revealed tile and its neighbors
+    SafeArea,
+}
+
+impl FirstClick {
+    // Are bombs placed after the first reveal?
+    pub fn is_deferred(&self) -> bool {
+        !matches!(self, FirstClick::Unprotected)
+    }
+
+    // Tiles which must stay bomb free when revealing `coordinates` first
+    pub fn safe_area(&self, tile_map: &TileMap, coordinates: Coordinates) -> Vec<Coordinates> {
+        match self {
+            FirstClick::Unprotected => Vec::new(),
+            FirstClick::SafeTile => vec![coordinates],
+            FirstClick::SafeArea => std::iter::once(coordinates)
+                .chain(tile_map.safe_square_at(coordinates))
+                .filter(|c| tile_map.tile(*c).is_some())
+                .collect(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BoardSize {
     pub columns: u16,
     pub rows: u16,
 }
 
+impl BoardSize {
+    pub fn tile_count(&self) -> u32 {
+        self.columns as u32 * self.rows as u32
+    }
+}
+
+//Classic difficulty presets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BoardPreset {
+    // 9x9 with 10 bombs
+    Beginner,
+    // 16x16 with 40 bombs
+    Intermediate,
+    // 30x16 with 99 bombs
+    Expert,
+}
+
+impl BoardPreset {
+    pub const ALL: [BoardPreset; 3] = [
+        BoardPreset::Beginner,
+        BoardPreset::Intermediate,
+        BoardPreset::Expert,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BoardPreset::Beginner => "Beginner",
+            BoardPreset::Intermediate => "Intermediate",
+            BoardPreset::Expert => "Expert",
+        }
+    }
+
+    pub fn map_size(&self) -> BoardSize {
+        match self {
+            BoardPreset::Beginner => BoardSize { columns: 9, rows: 9 },
+            BoardPreset::Intermediate => BoardSize { columns: 16, rows: 16 },
+            BoardPreset::Expert => BoardSize { columns: 30, rows: 16 },
+        }
+    }
+
+    pub fn bomb_count(&self) -> u16 {
+        match self {
+            BoardPreset::Beginner => 10,
+            BoardPreset::Intermediate => 40,
+            BoardPreset::Expert => 99,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Resource)]
 pub struct BoardOptions<T: FreelyMutableState> {
     // Tile map size
@@ -39,39 +131,200 @@ pub struct BoardOptions<T: FreelyMutableState> {
     pub tile_size: TileSize,
     // padding between tiles,
     pub tile_padding: f32,
+    // tile shape and neighborhood
+    #[serde(default)]
+    pub topology: BoardTopology,
+    // neighbors wrap around the board edges, like on a torus
+    #[serde(default)]
+    pub wrapping: bool,
+    // tiles the numbers count the bombs of, the adjacent ones by default
+    #[serde(default)]
+    pub neighborhood: Neighborhood,
+    // shape of the board, its holes hold no tile. Must match `map_size`
+    #[serde(default)]
+    pub mask: Option<BoardMask>,
+    // most bombs a single tile can hold, numbers and flags then count bomb quantities
+    #[serde(default = "default_max_bombs_per_tile")]
+    pub max_bombs_per_tile: u8,
     //makes the first click safe by shifting the mine 
     // to the first empty square on the top row, 
     // starting from the left corner.
     pub safe_start: bool,
+    //places the bombs once the first tile is revealed, keeping it safe.
+    // Replaces `safe_start` when enabled
+    pub first_click: FirstClick,
+    // marking a flagged tile puts a question mark on it instead of clearing it
+    #[serde(default)]
+    pub question_marks: bool,
+    // seed used to generate the board, a random one is picked when empty
+    pub seed: Option<u64>,
+    // random number generator used to place the bombs
+    #[serde(skip, default = "default_rng_source")]
+    pub rng_source: RngSource,
+    // bomb placement strategy
+    pub generation: BoardGeneration,
+    // file the game is saved to on pause and exit, and resumed from. No saves when empty
+    pub save_path: Option<PathBuf>,
+    // file the player statistics are persisted to. No stats recorded when empty
+    #[serde(default)]
+    pub stats_path: Option<PathBuf>,
+    // file the replay of the last finished game is written to. No replays when empty
+    #[serde(default)]
+    pub replay_path: Option<PathBuf>,
     //State with active game
     pub game_state: T,
     //State with paused game
     pub pause_state: T,
     //State with end game
     pub game_over_state: T,
+    //State with won game
+    pub won_state: T,
 }
 
 impl<T: FreelyMutableState> BoardOptions<T> {
-    pub fn tile_size_px(&self) -> f32 {
+    // Seed of the next board, either the configured one or a fresh random one
+    pub fn next_seed(&self) -> u64 {
+        self.seed.unwrap_or_else(|| thread_rng().gen())
+    }
+
+    // Is it a plain square board? Stats are only kept for those, keyed by size and bomb count
+    pub fn is_classic(&self) -> bool {
+        self.topology == BoardTopology::Square
+            && !self.wrapping
+            && self.neighborhood == Neighborhood::Adjacent
+            && self.mask.is_none()
+            && self.max_bombs_per_tile == 1
+    }
+
+    // Switches the board size and bomb count to the `preset` ones
+    pub fn apply_preset(&mut self, preset: BoardPreset) {
+        self.map_size = preset.map_size();
+        self.bomb_count = preset.bomb_count();
+        self.mask = None;
+    }
+
+    // Shapes the board like `mask`, switching to its size
+    pub fn apply_mask(&mut self, mask: BoardMask) {
+        self.map_size = BoardSize {
+            columns: mask.width(),
+            rows: mask.height(),
+        };
+        self.mask = Some(mask);
+    }
+
+    // Most bombs `map_size` can hold, keeping room for the first click safe tiles
+    pub fn max_bomb_count(&self, map_size: &BoardSize) -> u32 {
+        let safe_tiles = match self.first_click {
+            FirstClick::Unprotected | FirstClick::SafeTile => 1,
+            FirstClick::SafeArea => 1 + self.neighborhood.neighbor_count(self.topology) as u32,
+        };
+        let tile_count = match &self.mask {
+            Some(mask) => mask.tile_count(),
+            None => map_size.tile_count(),
+        };
+        tile_count.saturating_sub(safe_tiles) * self.max_bombs_per_tile as u32
+    }
+
+    // Checks the options describe a playable board, bomb placement never ends otherwise
+    pub fn validate(&self) -> Result<(), BoardConfigError> {
+        let BoardSize { columns, rows } = self.map_size;
+        if columns == 0 || rows == 0 {
+            return Err(BoardConfigError::EmptyBoard { columns, rows });
+        }
+        if let Some(mask) = &self.mask {
+            if (mask.width(), mask.height()) != (columns, rows) {
+                return Err(BoardConfigError::MaskSizeMismatch {
+                    mask_columns: mask.width(),
+                    mask_rows: mask.height(),
+                    columns,
+                    rows,
+                });
+            }
+        }
+        if self.max_bombs_per_tile == 0 {
+            return Err(BoardConfigError::NoBombsPerTile);
+        }
+        if self.wrapping && self.topology == BoardTopology::Hex && rows % 2 == 1 {
+            return Err(BoardConfigError::OddWrappingHexRows { rows });
+        }
+        if let Neighborhood::Offsets(offsets) = &self.neighborhood {
+            let mut unique_offsets = offsets.clone();
+            unique_offsets.sort();
+            unique_offsets.dedup();
+            if self.topology != BoardTopology::Square
+                || offsets.is_empty()
+                || offsets.contains(&(0, 0))
+                || unique_offsets.len() != offsets.len()
+            {
+                return Err(BoardConfigError::InvalidNeighborhood(self.neighborhood.clone()));
+            }
+        }
+        if let BoardGeneration::NoGuess { max_attempts: 0, .. } = self.generation {
+            return Err(BoardConfigError::NoGenerationAttempts);
+        }
+        let max_bomb_count = self.max_bomb_count(&self.map_size);
+        if self.bomb_count as u32 > max_bomb_count {
+            return Err(BoardConfigError::TooManyBombs {
+                bomb_count: self.bomb_count,
+                max_bomb_count,
+            });
+        }
+        let smallest_tile_size = match self.tile_size {
+            TileSize::Fixed(size) if size.is_finite() && size > 0.0 => size,
+            TileSize::Adaptive { min, max } if min.is_finite() && max.is_finite() && 0.0 < min && min <= max => min,
+            _ => return Err(BoardConfigError::InvalidTileSize(self.tile_size.clone())),
+        };
+        if !self.tile_padding.is_finite() || self.tile_padding < 0.0 {
+            return Err(BoardConfigError::NegativePadding(self.tile_padding));
+        }
+        if self.tile_padding >= smallest_tile_size {
+            return Err(BoardConfigError::PaddingTooLarge {
+                tile_padding: self.tile_padding,
+                tile_size: smallest_tile_size,
+            });
+        }
+        Ok(())
+    }
+
+    // Tile map without bombs of the configured size and topology
+    pub fn empty_tile_map(&self) -> TileMap {
+        let mut tile_map = TileMap::with_topology(self.map_size.columns, self.map_size.rows, self.topology);
+        tile_map.set_wrapping(self.wrapping);
+        tile_map.set_neighborhood(self.neighborhood.clone());
+        tile_map.set_max_bombs_per_tile(self.max_bombs_per_tile);
+        if let Some(mask) = &self.mask {
+            tile_map.set_mask(mask);
+        }
+        tile_map
+    }
+
+    // Random number generator for the given seed
+    pub fn rng(&self, seed: u64) -> Box<dyn RngCore> {
+        (self.rng_source)(seed)
+    }
+
+    // Tile size in pixels, adaptive sizes fit the board in `window_size`
+    pub fn tile_size_px(&self, window_size: Vec2) -> f32 {
         match self.tile_size {
             TileSize::Fixed(size) => size,
-            TileSize::Adaptive { .. } => panic!(
-                "Not supported in this commit due to WindowDescriptor is not available as resource"
-            )
+            TileSize::Adaptive { min, max } => {
+                let max_size = window_size / self.board_size_at(1.0);
+                max_size.x.min(max_size.y).clamp(min, max)
+            }
         }
     }
 
-    pub fn board_size(&self) -> Vec2 {
-        let tile_size_pixels = self.tile_size_px();
-        Vec2::new(
-            self.map_size.columns as f32 * tile_size_pixels,
-            self.map_size.rows as f32 * tile_size_pixels
-        )
+    pub fn board_size(&self, window_size: Vec2) -> Vec2 {
+        self.board_size_at(self.tile_size_px(window_size))
+    }
+
+    fn board_size_at(&self, tile_size: f32) -> Vec2 {
+        layout::board_size(self.topology, self.map_size.columns, self.map_size.rows, tile_size)
     }
 
     // We define the board anchor position (bottom left)
-    pub fn board_position_px(&self, z_layer: f32) -> Vec3 {
-        let board_size = self.board_size();
+    pub fn board_position_px(&self, z_layer: f32, window_size: Vec2) -> Vec3 {
+        let board_size = self.board_size(window_size);
         
         match self.position {
             BoardPosition::Centered { offset } => {
@@ -86,6 +339,87 @@ impl<T: FreelyMutableState> BoardOptions<T> {
     }
 }
 
+/// Reason why `BoardOptions` can't describe a playable board
+#[derive(Debug, Clone, PartialEq)]
+pub enum BoardConfigError {
+    /// The board has no tiles
+    EmptyBoard { columns: u16, rows: u16 },
+    /// The mask isn't the size of the board
+    MaskSizeMismatch { mask_columns: u16, mask_rows: u16, columns: u16, rows: u16 },
+    /// Tiles must be able to hold at least one bomb
+    NoBombsPerTile,
+    /// Odd rows are shifted on hex boards, wrapping the last row onto the first one needs an even row count
+    OddWrappingHexRows { rows: u16 },
+    /// No-guess generation must be allowed at least one attempt
+    NoGenerationAttempts,
+    /// Neighborhood offsets must be distinct, not empty nor `(0, 0)`, and are only supported on square boards
+    InvalidNeighborhood(Neighborhood),
+    /// The bombs don't fit on the board next to the first click safe tiles
+    TooManyBombs { bomb_count: u16, max_bomb_count: u32 },
+    /// Tile sizes must be positive, adaptive ones with `min <= max`
+    InvalidTileSize(TileSize),
+    /// The padding is negative or not a number
+    NegativePadding(f32),
+    /// The padding hides the tiles
+    PaddingTooLarge { tile_padding: f32, tile_size: f32 },
+}
+
+impl Display for BoardConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            BoardConfigError::EmptyBoard { columns, rows } => {
+                write!(f, "a {}x{} board has no tiles", columns, rows)
+            }
+            BoardConfigError::MaskSizeMismatch { mask_columns, mask_rows, columns, rows } => write!(
+                f,
+                "a {}x{} mask doesn't fit a {}x{} board",
+                mask_columns, mask_rows, columns, rows
+            ),
+            BoardConfigError::NoBombsPerTile => write!(f, "tiles must hold at least one bomb"),
+            BoardConfigError::OddWrappingHexRows { rows } => {
+                write!(f, "a wrapping hex board needs an even row count, not {}", rows)
+            }
+            BoardConfigError::NoGenerationAttempts => {
+                write!(f, "no-guess generation needs at least one attempt")
+            }
+            BoardConfigError::InvalidNeighborhood(neighborhood) => {
+                write!(f, "invalid neighborhood {:?}", neighborhood)
+            }
+            BoardConfigError::TooManyBombs { bomb_count, max_bomb_count } => write!(
+                f,
+                "{} bombs don't fit on the board, at most {} do",
+                bomb_count, max_bomb_count
+            ),
+            BoardConfigError::InvalidTileSize(tile_size) => {
+                write!(f, "invalid tile size {:?}", tile_size)
+            }
+            BoardConfigError::NegativePadding(tile_padding) => {
+                write!(f, "invalid tile padding {}", tile_padding)
+            }
+            BoardConfigError::PaddingTooLarge { tile_padding, tile_size } => write!(
+                f,
+                "tile padding {} must be smaller than the tile size {}",
+                tile_padding, tile_size
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BoardConfigError {}
+
+/// Default rng source, a seeded `StdRng`
+pub fn seeded_std_rng(seed: u64) -> Box<dyn RngCore> {
+    Box::new(StdRng::seed_from_u64(seed))
+}
+
+fn default_rng_source() -> RngSource {
+    seeded_std_rng
+}
+
+pub(crate) fn default_max_bombs_per_tile() -> u8 {
+    1
+}
+
 impl Default for TileSize {
     fn default() -> Self {
         Self::Adaptive {
@@ -115,4 +449,192 @@ impl Default for BoardPosition {
 //             game_state: Default::default(),
 //         }
 //     }
-// }
\ No newline at end of file
+// }
+/// Game states of the tests
+#[cfg(test)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, bevy::prelude::States, Serialize, Deserialize)]
+pub(crate) enum TestState {
+    #[default]
+    Game,
+    Pause,
+    Over,
+    Won,
+}
+
+#[cfg(test)]
+impl BoardOptions<TestState> {
+    /// Options of a `columns` x `rows` board with random bombs and no save files
+    pub(crate) fn test(columns: u16, rows: u16, bomb_count: u16) -> Self {
+        Self {
+            map_size: BoardSize { columns, rows },
+            bomb_count,
+            position: BoardPosition::default(),
+            tile_size: TileSize::default(),
+            tile_padding: 1.0,
+            topology: BoardTopology::Square,
+            wrapping: false,
+            neighborhood: Neighborhood::Adjacent,
+            mask: None,
+            max_bombs_per_tile: 1,
+            safe_start: false,
+            first_click: FirstClick::SafeArea,
+            question_marks: false,
+            seed: None,
+            rng_source: seeded_std_rng,
+            generation: BoardGeneration::Random,
+            save_path: None,
+            stats_path: None,
+            replay_path: None,
+            game_state: TestState::Game,
+            pause_state: TestState::Pause,
+            game_over_state: TestState::Over,
+            won_state: TestState::Won,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use crate::resources::NoGuessFallback;
+
+    #[test]
+    fn first_click_safe_area_stays_on_the_map() {
+        let tile_map = TileMap::empty(5, 5);
+        let center = Coordinates { coord_x: 2, coord_y: 2 };
+        let corner = Coordinates { coord_x: 0, coord_y: 0 };
+        assert!(FirstClick::Unprotected.safe_area(&tile_map, center).is_empty());
+        assert_eq!(FirstClick::SafeTile.safe_area(&tile_map, center), vec![center]);
+        assert_eq!(FirstClick::SafeArea.safe_area(&tile_map, center).len(), 9);
+        let corner_area = FirstClick::SafeArea.safe_area(&tile_map, corner);
+        assert_eq!(corner_area[0], corner);
+        assert_eq!(corner_area.len(), 4);
+    }
+
+    #[test]
+    fn playable_options_are_valid() {
+        assert_eq!(BoardOptions::test(9, 9, 10).validate(), Ok(()));
+        assert_eq!(BoardOptions::test(9, 9, 72).validate(), Ok(()));
+    }
+
+    #[test]
+    fn empty_boards_are_rejected() {
+        assert_eq!(
+            BoardOptions::test(0, 9, 0).validate(),
+            Err(BoardConfigError::EmptyBoard { columns: 0, rows: 9 })
+        );
+        assert_eq!(
+            BoardOptions::test(9, 0, 0).validate(),
+            Err(BoardConfigError::EmptyBoard { columns: 9, rows: 0 })
+        );
+    }
+
+    #[test]
+    fn bombs_leave_room_for_the_safe_start() {
+        let mut board_options = BoardOptions::test(9, 9, 73);
+        assert_eq!(
+            board_options.validate(),
+            Err(BoardConfigError::TooManyBombs { bomb_count: 73, max_bomb_count: 72 })
+        );
+        board_options.first_click = FirstClick::SafeTile;
+        assert_eq!(board_options.validate(), Ok(()));
+        assert_eq!(board_options.max_bomb_count(&board_options.map_size), 80);
+        // The safe area covers the whole 2x2 board
+        assert_eq!(BoardOptions::test(2, 2, 0).max_bomb_count(&BoardSize { columns: 2, rows: 2 }), 0);
+    }
+
+    #[test]
+    fn no_guess_generation_needs_an_attempt() {
+        let mut board_options = BoardOptions::test(9, 9, 10);
+        board_options.generation = BoardGeneration::NoGuess {
+            max_attempts: 0,
+            max_duration: Duration::from_secs(1),
+            fallback: NoGuessFallback::UseLastAttempt,
+        };
+        assert_eq!(board_options.validate(), Err(BoardConfigError::NoGenerationAttempts));
+    }
+
+    #[test]
+    fn tile_sizes_and_padding_are_checked() {
+        let mut board_options = BoardOptions::test(9, 9, 10);
+        board_options.tile_size = TileSize::Adaptive { min: 20.0, max: 10.0 };
+        assert!(matches!(board_options.validate(), Err(BoardConfigError::InvalidTileSize(_))));
+        board_options.tile_size = TileSize::Fixed(10.0);
+        board_options.tile_padding = 10.0;
+        assert!(matches!(board_options.validate(), Err(BoardConfigError::PaddingTooLarge { .. })));
+        board_options.tile_padding = -1.0;
+        assert_eq!(board_options.validate(), Err(BoardConfigError::NegativePadding(-1.0)));
+    }
+
+    #[test]
+    fn hex_safe_area_keeps_the_six_neighbors() {
+        let mut board_options = BoardOptions::test(9, 9, 74);
+        assert!(board_options.validate().is_err());
+        board_options.topology = BoardTopology::Hex;
+        assert_eq!(board_options.max_bomb_count(&board_options.map_size), 74);
+        assert_eq!(board_options.validate(), Ok(()));
+        assert!(!board_options.is_classic());
+    }
+
+    #[test]
+    fn wrapping_hex_boards_need_even_rows() {
+        let mut board_options = BoardOptions::test(9, 9, 10);
+        board_options.topology = BoardTopology::Hex;
+        board_options.wrapping = true;
+        assert_eq!(board_options.validate(), Err(BoardConfigError::OddWrappingHexRows { rows: 9 }));
+        board_options.map_size = BoardSize { columns: 9, rows: 10 };
+        assert_eq!(board_options.validate(), Ok(()));
+        assert!(!board_options.is_classic());
+    }
+
+    #[test]
+    fn neighborhood_offsets_are_checked() {
+        let mut board_options = BoardOptions::test(9, 9, 10);
+        board_options.neighborhood = Neighborhood::knight();
+        assert_eq!(board_options.validate(), Ok(()));
+        assert!(!board_options.is_classic());
+        for offsets in [vec![], vec![(0, 0)], vec![(1, 0), (1, 0)]] {
+            board_options.neighborhood = Neighborhood::Offsets(offsets);
+            assert_eq!(
+                board_options.validate(),
+                Err(BoardConfigError::InvalidNeighborhood(board_options.neighborhood.clone()))
+            );
+        }
+        board_options.neighborhood = Neighborhood::orthogonal();
+        board_options.topology = BoardTopology::Hex;
+        assert!(board_options.validate().is_err());
+    }
+
+    #[test]
+    fn masks_set_the_size_and_the_bomb_room() {
+        let mut board_options = BoardOptions::test(9, 9, 4);
+        board_options.first_click = FirstClick::SafeTile;
+        board_options.apply_mask("#.#\n###".parse().unwrap());
+        assert_eq!((board_options.map_size.columns, board_options.map_size.rows), (3, 2));
+        assert_eq!(board_options.validate(), Ok(()));
+        assert!(!board_options.is_classic());
+        board_options.bomb_count = 5;
+        assert!(board_options.validate().is_err());
+        board_options.bomb_count = 4;
+        board_options.map_size = BoardSize { columns: 4, rows: 2 };
+        assert_eq!(
+            board_options.validate(),
+            Err(BoardConfigError::MaskSizeMismatch { mask_columns: 3, mask_rows: 2, columns: 4, rows: 2 })
+        );
+    }
+
+    #[test]
+    fn tiles_holding_several_bombs_raise_the_bomb_room() {
+        let mut board_options = BoardOptions::test(9, 9, 150);
+        assert!(board_options.validate().is_err());
+        board_options.max_bombs_per_tile = 2;
+        assert_eq!(board_options.max_bomb_count(&board_options.map_size), 144);
+        assert!(board_options.validate().is_err());
+        board_options.bomb_count = 144;
+        assert_eq!(board_options.validate(), Ok(()));
+        assert!(!board_options.is_classic());
+        board_options.max_bombs_per_tile = 0;
+        assert_eq!(board_options.validate(), Err(BoardConfigError::NoBombsPerTile));
+    }
+}
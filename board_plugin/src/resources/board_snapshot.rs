@@ -0,0 +1,133 @@
+use std::path::Path;
+use std::time::Duration;
+use bevy::state::state::FreelyMutableState;
+use minesweeper_core::Game;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use crate::resources::{save_file, Board, BoardOptions, GameTimer, SaveFileError};
+
+/// Version of the save file format, bumped on every incompatible change
+pub const SNAPSHOT_VERSION: u32 = 2;
+
+/// Saved board, enough to rebuild the board entities and resume the game
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoardSnapshot<T: FreelyMutableState> {
+    /// Save file format version
+    pub version: u32,
+    /// Options the board was created with
+    pub options: BoardOptions<T>,
+    /// Seed the tile map was generated from
+    pub seed: u64,
+    /// Are the bombs placed? False until the first reveal when bomb placement is deferred
+    pub bombs_placed: bool,
+    /// Tile map, covered and flagged tiles
+    pub game: Game,
+    /// Time spent playing
+    pub elapsed: Duration,
+    /// Was an action undone or a hint shown?
+    #[serde(default)]
+    pub assisted: bool,
+}
+
+impl<T> BoardSnapshot<T>
+where
+    T: FreelyMutableState + Serialize + DeserializeOwned,
+{
+    pub fn new(board: &Board, options: &BoardOptions<T>, game_timer: &GameTimer) -> Self {
+        Self {
+            version: SNAPSHOT_VERSION,
+            options: options.clone(),
+            seed: board.seed,
+            bombs_placed: board.bombs_placed,
+            game: board.game.clone(),
+            elapsed: game_timer.elapsed(),
+            assisted: board.assisted,
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), SaveFileError> {
+        save_file::write(path, self)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, SaveFileError> {
+        save_file::read_versioned(path, SNAPSHOT_VERSION)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+    use minesweeper_core::tile_map::TileMap;
+    use crate::components::Coordinates;
+    use crate::resources::{seeded_std_rng, TestState};
+
+    fn options() -> BoardOptions<TestState> {
+        BoardOptions {
+            seed: Some(7),
+            ..BoardOptions::test(4, 3, 2)
+        }
+    }
+
+    fn board() -> Board {
+        let mut tile_map = TileMap::empty(4, 3);
+        tile_map.set_bombs(2, &mut *seeded_std_rng(7));
+        let mut game = Game::new(tile_map);
+        game.toggle_flag(Coordinates { coord_x: 3, coord_y: 2 });
+        Board {
+            seed: 7,
+            assisted: true,
+            ..Board::detached(game)
+        }
+    }
+
+    fn save_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("minesweeper_{}_{}.ron", name, std::process::id()))
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let path = save_path("round_trip");
+        let board = board();
+        BoardSnapshot::new(&board, &options(), &GameTimer::resumed(Duration::from_secs(42))).save(&path).unwrap();
+        let loaded = BoardSnapshot::<TestState>::load(&path);
+        fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.version, SNAPSHOT_VERSION);
+        assert_eq!(loaded.seed, 7);
+        assert!(loaded.bombs_placed);
+        assert!(loaded.assisted);
+        assert_eq!(loaded.elapsed, Duration::from_secs(42));
+        assert_eq!(loaded.options.seed, Some(7));
+        assert_eq!(loaded.options.won_state, TestState::Won);
+        for coord_y in 0..3 {
+            for coord_x in 0..4 {
+                let coordinates = Coordinates { coord_x, coord_y };
+                assert_eq!(
+                    loaded.game.tile_map().is_bomb_at(coordinates),
+                    board.game.tile_map().is_bomb_at(coordinates)
+                );
+            }
+        }
+        assert!(loaded.game.is_flagged(&Coordinates { coord_x: 3, coord_y: 2 }));
+        assert_eq!(loaded.game.covered().count(), board.game.covered().count());
+    }
+
+    #[test]
+    fn other_versions_are_rejected() {
+        let path = save_path("version");
+        let mut snapshot = BoardSnapshot::new(&board(), &options(), &GameTimer::default());
+        snapshot.version = SNAPSHOT_VERSION + 1;
+        snapshot.save(&path).unwrap();
+        let loaded = BoardSnapshot::<TestState>::load(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(loaded, Err(SaveFileError::Version { found, expected }) if found == SNAPSHOT_VERSION + 1 && expected == SNAPSHOT_VERSION));
+    }
+
+    #[test]
+    fn missing_file_is_an_io_error() {
+        let loaded = BoardSnapshot::<TestState>::load(&save_path("missing"));
+        assert!(matches!(loaded, Err(SaveFileError::Io(_))));
+    }
+}
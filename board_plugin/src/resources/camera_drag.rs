@@ -0,0 +1,43 @@
+use bevy::prelude::{Resource, Vec2};
+
+/// Cursor travel, in logical pixels, before a left button press becomes a drag
+const DRAG_THRESHOLD: f32 = 5.0;
+
+/// Left button drag panning the camera. A press turning into a drag doesn't reveal its tile
+#[derive(Debug, Clone, Default, Resource)]
+pub struct CameraDrag {
+    start: Option<Vec2>,
+    last: Vec2,
+    dragging: bool,
+}
+
+impl CameraDrag {
+    pub fn press(&mut self, cursor: Vec2) {
+        self.start = Some(cursor);
+        self.last = cursor;
+        self.dragging = false;
+    }
+
+    /// Moves the cursor while the button is held, returns how far to pan once dragging
+    pub fn drag_to(&mut self, cursor: Vec2) -> Option<Vec2> {
+        let start = self.start?;
+        if !self.dragging && cursor.distance(start) > DRAG_THRESHOLD {
+            self.dragging = true;
+        }
+        if !self.dragging {
+            return None;
+        }
+        let delta = cursor - self.last;
+        self.last = cursor;
+        Some(delta)
+    }
+
+    pub fn release(&mut self) {
+        self.start = None;
+        self.dragging = false;
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.dragging
+    }
+}
@@ -0,0 +1,179 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+use bevy::prelude::Resource;
+use serde::{Deserialize, Serialize};
+use crate::resources::{save_file, SaveFileError};
+
+/// Version of the stats file format, bumped on every incompatible change
+pub const STATS_VERSION: u32 = 1;
+
+/// Board configuration the stats are recorded for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct StatsKey {
+    pub columns: u16,
+    pub rows: u16,
+    pub bomb_count: u16,
+}
+
+/// Records of every game played on one board configuration
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConfigurationStats {
+    pub games_played: u32,
+    pub games_won: u32,
+    /// Games won in a row, up to the last game
+    pub current_streak: u32,
+    pub best_streak: u32,
+    /// Fastest win
+    pub best_time: Option<Duration>,
+    /// Best 3BV per second of a win, the board difficulty over the time spent
+    pub best_three_bv_per_second: Option<f32>,
+    /// Games ended after undoing an action or showing a hint. They count neither as played games nor for records
+    #[serde(default)]
+    pub assisted_games: u32,
+    #[serde(default)]
+    pub assisted_wins: u32,
+}
+
+impl ConfigurationStats {
+    pub fn record_loss(&mut self) {
+        self.games_played += 1;
+        self.current_streak = 0;
+    }
+
+    /// Records a win in `time` on a board of the given 3BV
+    pub fn record_win(&mut self, time: Duration, three_bv: u32) {
+        self.games_played += 1;
+        self.games_won += 1;
+        self.current_streak += 1;
+        self.best_streak = self.best_streak.max(self.current_streak);
+        // Boards won without a timed reveal have no meaningful time
+        if time.is_zero() {
+            return;
+        }
+        self.best_time = Some(self.best_time.map_or(time, |best| best.min(time)));
+        let three_bv_per_second = three_bv as f32 / time.as_secs_f32();
+        self.best_three_bv_per_second = Some(
+            self.best_three_bv_per_second
+                .map_or(three_bv_per_second, |best| best.max(three_bv_per_second)),
+        );
+    }
+
+    pub fn record_assisted(&mut self, won: bool) {
+        self.assisted_games += 1;
+        if won {
+            self.assisted_wins += 1;
+        }
+    }
+
+    pub fn win_ratio(&self) -> f32 {
+        if self.games_played == 0 {
+            return 0.0;
+        }
+        self.games_won as f32 / self.games_played as f32
+    }
+}
+
+/// Content of the stats file
+#[derive(Serialize, Deserialize)]
+struct StatsFile {
+    version: u32,
+    records: BTreeMap<StatsKey, ConfigurationStats>,
+}
+
+/// Player statistics per board configuration, persisted to the options `stats_path`
+#[derive(Debug, Clone, Default, Resource)]
+pub struct GameStats {
+    records: BTreeMap<StatsKey, ConfigurationStats>,
+}
+
+impl GameStats {
+    pub fn get(&self, key: &StatsKey) -> Option<&ConfigurationStats> {
+        self.records.get(key)
+    }
+
+    pub fn get_mut(&mut self, key: StatsKey) -> &mut ConfigurationStats {
+        self.records.entry(key).or_default()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&StatsKey, &ConfigurationStats)> {
+        self.records.iter()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), SaveFileError> {
+        save_file::write(
+            path,
+            &StatsFile {
+                version: STATS_VERSION,
+                records: self.records.clone(),
+            },
+        )
+    }
+
+    pub fn load(path: &Path) -> Result<Self, SaveFileError> {
+        let file: StatsFile = save_file::read_versioned(path, STATS_VERSION)?;
+        Ok(Self { records: file.records })
+    }
+
+    /// Stats stored at `path`, empty ones when there are none yet
+    pub fn load_or_default(path: &Path) -> Result<Self, SaveFileError> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Self::load(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wins_keep_the_best_time_and_streak() {
+        let mut stats = ConfigurationStats::default();
+        stats.record_win(Duration::from_secs(20), 10);
+        stats.record_win(Duration::from_secs(10), 10);
+        stats.record_win(Duration::from_secs(40), 10);
+        assert_eq!(stats.games_won, 3);
+        assert_eq!(stats.best_time, Some(Duration::from_secs(10)));
+        assert_eq!(stats.best_three_bv_per_second, Some(1.0));
+        assert_eq!(stats.best_streak, 3);
+        stats.record_loss();
+        stats.record_win(Duration::from_secs(30), 10);
+        assert_eq!(stats.current_streak, 1);
+        assert_eq!(stats.best_streak, 3);
+        assert_eq!(stats.games_played, 5);
+    }
+
+    #[test]
+    fn untimed_wins_have_no_best_time() {
+        let mut stats = ConfigurationStats::default();
+        stats.record_win(Duration::ZERO, 10);
+        assert_eq!(stats.games_won, 1);
+        assert_eq!(stats.best_time, None);
+        assert_eq!(stats.best_three_bv_per_second, None);
+    }
+
+    #[test]
+    fn win_ratio_counts_every_game() {
+        let mut stats = ConfigurationStats::default();
+        assert_eq!(stats.win_ratio(), 0.0);
+        stats.record_win(Duration::from_secs(5), 3);
+        stats.record_loss();
+        stats.record_loss();
+        stats.record_loss();
+        assert_eq!(stats.win_ratio(), 0.25);
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let path = std::env::temp_dir().join(format!("minesweeper_stats_{}.ron", std::process::id()));
+        let key = StatsKey { columns: 9, rows: 9, bomb_count: 10 };
+        let mut stats = GameStats::default();
+        stats.get_mut(key).record_win(Duration::from_secs(12), 20);
+        stats.save(&path).unwrap();
+        let loaded = GameStats::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap().get(&key), stats.get(&key));
+    }
+}
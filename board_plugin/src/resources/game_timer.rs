@@ -0,0 +1,42 @@
+use std::time::Duration;
+use bevy::prelude::Resource;
+
+/// Time spent playing the current board.
+/// Starts on the first reveal and stops once the game ended
+#[derive(Debug, Clone, Default, Resource)]
+pub struct GameTimer {
+    elapsed: Duration,
+    running: bool,
+}
+
+impl GameTimer {
+    /// Timer of a resumed game, already running if any time was spent on it
+    pub fn resumed(elapsed: Duration) -> Self {
+        Self {
+            elapsed,
+            running: !elapsed.is_zero(),
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    pub fn start(&mut self) {
+        self.running = true;
+    }
+
+    pub fn stop(&mut self) {
+        self.running = false;
+    }
+
+    pub fn tick(&mut self, delta: Duration) {
+        if self.running {
+            self.elapsed += delta;
+        }
+    }
+}
\ No newline at end of file
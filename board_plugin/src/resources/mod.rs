@@ -1,8 +1,24 @@
 pub use board_options::*;
 pub use board::*;
 pub use board_assets::*;
-pub mod tile;
-pub mod tile_map;
+pub use board_snapshot::*;
+pub use game_timer::*;
+pub use game_stats::*;
+pub use replay::*;
+pub use probability_overlay::ProbabilityOverlay;
+pub use board_cursor::BoardCursor;
+pub use camera_drag::CameraDrag;
+pub use save_file::SaveFileError;
+pub use minesweeper_core::{BoardGeneration, BoardMask, BoardMaskError, BoardTopology, Neighborhood, NoGuessFallback};
+pub use minesweeper_core::{solver, tile, tile_map};
 mod board_options;
 mod board;
+mod board_snapshot;
+mod game_timer;
+mod game_stats;
+mod replay;
+mod probability_overlay;
+mod board_cursor;
+mod camera_drag;
+mod save_file;
 pub mod board_assets;
\ No newline at end of file
@@ -0,0 +1,7 @@
+use bevy::prelude::Resource;
+
+/// Shows the mine probability of every covered tile when enabled
+#[derive(Debug, Clone, Default, Resource)]
+pub struct ProbabilityOverlay {
+    pub enabled: bool,
+}
@@ -0,0 +1,294 @@
+use std::path::Path;
+use std::time::Duration;
+use bevy::prelude::Resource;
+use bevy::state::state::FreelyMutableState;
+use serde::{Deserialize, Serialize};
+use crate::components::Coordinates;
+use crate::resources::board_options::default_max_bombs_per_tile;
+use crate::resources::{save_file, BoardGeneration, BoardMask, BoardOptions, BoardSize, BoardTopology, FirstClick, Neighborhood, SaveFileError};
+
+/// Version of the replay file format, bumped on every incompatible change
+pub const REPLAY_VERSION: u32 = 1;
+
+/// Recorded player input
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReplayInput {
+    Reveal(Coordinates),
+    Mark(Coordinates),
+    Chord(Coordinates),
+    Undo,
+    Redo,
+}
+
+/// Player input with the milliseconds elapsed since the board was created
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplayAction {
+    pub time_ms: u32,
+    pub input: ReplayInput,
+}
+
+/// Recorded game: the options and seed generating the board, then every input
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Replay {
+    /// Replay file format version
+    pub version: u32,
+    pub map_size: BoardSize,
+    pub bomb_count: u16,
+    #[serde(default)]
+    pub topology: BoardTopology,
+    #[serde(default)]
+    pub wrapping: bool,
+    #[serde(default)]
+    pub neighborhood: Neighborhood,
+    #[serde(default)]
+    pub mask: Option<BoardMask>,
+    #[serde(default = "default_max_bombs_per_tile")]
+    pub max_bombs_per_tile: u8,
+    pub safe_start: bool,
+    pub first_click: FirstClick,
+    #[serde(default)]
+    pub question_marks: bool,
+    pub generation: BoardGeneration,
+    /// Seed the tile map was generated from
+    pub seed: u64,
+    pub actions: Vec<ReplayAction>,
+}
+
+impl Replay {
+    /// Empty replay of a board created from `options` and `seed`
+    pub fn new<T: FreelyMutableState>(options: &BoardOptions<T>, seed: u64) -> Self {
+        Self {
+            version: REPLAY_VERSION,
+            map_size: options.map_size.clone(),
+            bomb_count: options.bomb_count,
+            topology: options.topology,
+            wrapping: options.wrapping,
+            neighborhood: options.neighborhood.clone(),
+            mask: options.mask.clone(),
+            max_bombs_per_tile: options.max_bombs_per_tile,
+            safe_start: options.safe_start,
+            first_click: options.first_click,
+            question_marks: options.question_marks,
+            generation: options.generation.clone(),
+            seed,
+            actions: Vec::new(),
+        }
+    }
+
+    /// Sets the board options generating the recorded board.
+    /// The same rng source must be configured to get the same board back
+    pub fn apply_options<T: FreelyMutableState>(&self, options: &mut BoardOptions<T>) {
+        options.map_size = self.map_size.clone();
+        options.bomb_count = self.bomb_count;
+        options.topology = self.topology;
+        options.wrapping = self.wrapping;
+        options.neighborhood = self.neighborhood.clone();
+        options.mask = self.mask.clone();
+        options.max_bombs_per_tile = self.max_bombs_per_tile;
+        options.safe_start = self.safe_start;
+        options.first_click = self.first_click;
+        options.question_marks = self.question_marks;
+        options.generation = self.generation.clone();
+    }
+
+    /// Time of the last action
+    pub fn duration(&self) -> Duration {
+        self.actions
+            .last()
+            .map(|action| Duration::from_millis(action.time_ms as u64))
+            .unwrap_or_default()
+    }
+
+    /// Saves the replay without pretty printing, to keep files small
+    pub fn save(&self, path: &Path) -> Result<(), SaveFileError> {
+        save_file::write_compact(path, self)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, SaveFileError> {
+        save_file::read_versioned(path, REPLAY_VERSION)
+    }
+}
+
+/// Records the inputs of the current board
+#[derive(Debug, Clone, Resource)]
+pub struct ReplayRecorder {
+    replay: Replay,
+    elapsed: Duration,
+}
+
+impl ReplayRecorder {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    pub fn tick(&mut self, delta: Duration) {
+        self.elapsed += delta;
+    }
+
+    pub fn record(&mut self, input: ReplayInput) {
+        self.replay.actions.push(ReplayAction {
+            time_ms: self.elapsed.as_millis().min(u32::MAX as u128) as u32,
+            input,
+        });
+    }
+}
+
+/// Board options of the player, set aside while a replay plays with the recorded ones
+#[derive(Debug, Clone, Resource)]
+pub struct PlayerOptions<T: FreelyMutableState>(pub BoardOptions<T>);
+
+/// Plays a replay back. Inserting it before entering the game state creates the recorded board
+/// and replaces the player input
+#[derive(Debug, Clone, Resource)]
+pub struct ReplayPlayer {
+    replay: Replay,
+    elapsed: Duration,
+    next_action: usize,
+    speed: f32,
+    paused: bool,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            elapsed: Duration::ZERO,
+            next_action: 0,
+            speed: 1.0,
+            paused: false,
+        }
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    /// Has every action been played?
+    pub fn is_finished(&self) -> bool {
+        self.next_action >= self.replay.actions.len()
+    }
+
+    /// Advances the replay by `delta` at the current speed, returning the inputs due meanwhile
+    pub fn advance(&mut self, delta: Duration) -> Vec<ReplayInput> {
+        if self.paused {
+            return Vec::new();
+        }
+        self.elapsed += delta.mul_f32(self.speed);
+        let elapsed_ms = self.elapsed.as_millis();
+        let due = self.replay.actions[self.next_action..]
+            .iter()
+            .take_while(|action| action.time_ms as u128 <= elapsed_ms)
+            .map(|action| action.input)
+            .collect::<Vec<_>>();
+        self.next_action += due.len();
+        due
+    }
+
+    /// Jumps to the next action and returns its input
+    pub fn step(&mut self) -> Option<ReplayInput> {
+        let action = self.replay.actions.get(self.next_action)?;
+        self.next_action += 1;
+        self.elapsed = self.elapsed.max(Duration::from_millis(action.time_ms as u64));
+        Some(action.input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::TestState;
+
+    fn replay() -> Replay {
+        let mut recorder = ReplayRecorder::new(Replay::new(&BoardOptions::test(9, 9, 10), 3));
+        let inputs = [
+            (100, ReplayInput::Reveal(Coordinates { coord_x: 4, coord_y: 4 })),
+            (250, ReplayInput::Mark(Coordinates { coord_x: 0, coord_y: 0 })),
+            (250, ReplayInput::Undo),
+            (1000, ReplayInput::Chord(Coordinates { coord_x: 4, coord_y: 4 })),
+        ];
+        let mut elapsed = 0;
+        for (time_ms, input) in inputs {
+            recorder.tick(Duration::from_millis(time_ms - elapsed));
+            elapsed = time_ms;
+            recorder.record(input);
+        }
+        recorder.replay().clone()
+    }
+
+    #[test]
+    fn recorder_timestamps_the_inputs() {
+        let replay = replay();
+        let times: Vec<u32> = replay.actions.iter().map(|action| action.time_ms).collect();
+        assert_eq!(times, vec![100, 250, 250, 1000]);
+        assert_eq!(replay.duration(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn advance_plays_the_due_inputs_at_the_speed() {
+        let mut player = ReplayPlayer::new(replay());
+        assert!(player.advance(Duration::from_millis(50)).is_empty());
+        assert_eq!(player.advance(Duration::from_millis(50)).len(), 1);
+        player.set_speed(2.0);
+        // 100ms at double speed reach 300ms, both actions at 250ms are due
+        assert_eq!(
+            player.advance(Duration::from_millis(100)),
+            vec![ReplayInput::Mark(Coordinates { coord_x: 0, coord_y: 0 }), ReplayInput::Undo]
+        );
+        player.toggle_pause();
+        assert!(player.advance(Duration::from_secs(10)).is_empty());
+        assert!(!player.is_finished());
+        player.toggle_pause();
+        assert_eq!(player.advance(Duration::from_secs(1)).len(), 1);
+        assert!(player.is_finished());
+        assert!(player.advance(Duration::from_secs(1)).is_empty());
+    }
+
+    #[test]
+    fn step_jumps_to_the_next_input() {
+        let mut player = ReplayPlayer::new(replay());
+        player.toggle_pause();
+        assert_eq!(player.step(), Some(ReplayInput::Reveal(Coordinates { coord_x: 4, coord_y: 4 })));
+        assert_eq!(player.step(), Some(ReplayInput::Mark(Coordinates { coord_x: 0, coord_y: 0 })));
+        player.toggle_pause();
+        // Stepping moved the replay time to 250ms, resuming plays what comes after
+        assert_eq!(player.advance(Duration::ZERO), vec![ReplayInput::Undo]);
+        assert_eq!(player.step(), Some(ReplayInput::Chord(Coordinates { coord_x: 4, coord_y: 4 })));
+        assert_eq!(player.step(), None);
+        assert!(player.is_finished());
+    }
+
+    #[test]
+    fn apply_options_restores_the_recorded_board() {
+        let mut recorded = BoardOptions::test(16, 16, 40);
+        recorded.first_click = FirstClick::SafeTile;
+        let replay = Replay::new(&recorded, 3);
+        let mut options: BoardOptions<TestState> = BoardOptions::test(9, 9, 10);
+        replay.apply_options(&mut options);
+        assert_eq!((options.map_size.columns, options.map_size.rows), (16, 16));
+        assert_eq!(options.bomb_count, 40);
+        assert_eq!(options.first_click, FirstClick::SafeTile);
+    }
+}
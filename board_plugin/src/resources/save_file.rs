@@ -0,0 +1,75 @@
+use std::{fmt, fs, io};
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Only the version, read first so files of other versions are rejected with a clear error
+#[derive(Deserialize)]
+struct VersionHeader {
+    version: u32,
+}
+
+#[derive(Debug)]
+pub enum SaveFileError {
+    /// Reading or writing the file failed
+    Io(io::Error),
+    /// The file content is not valid
+    Format(ron::Error),
+    /// The file was saved with another format version
+    Version { found: u32, expected: u32 },
+}
+
+/// Reads a RON file holding a `version` field, which must match `version`
+pub(crate) fn read_versioned<T: DeserializeOwned>(path: &Path, version: u32) -> Result<T, SaveFileError> {
+    let content = fs::read_to_string(path)?;
+    let header: VersionHeader = ron::from_str(&content).map_err(ron::Error::from)?;
+    if header.version != version {
+        return Err(SaveFileError::Version {
+            found: header.version,
+            expected: version,
+        });
+    }
+    Ok(ron::from_str(&content).map_err(ron::Error::from)?)
+}
+
+/// Writes `value` as a RON file
+pub(crate) fn write<T: Serialize>(path: &Path, value: &T) -> Result<(), SaveFileError> {
+    let content = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())?;
+    fs::write(path, content)?;
+    Ok(())
+}
+
+/// Writes `value` as a single line RON file
+pub(crate) fn write_compact<T: Serialize>(path: &Path, value: &T) -> Result<(), SaveFileError> {
+    fs::write(path, ron::to_string(value)?)?;
+    Ok(())
+}
+
+impl From<io::Error> for SaveFileError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<ron::Error> for SaveFileError {
+    fn from(error: ron::Error) -> Self {
+        Self::Format(error)
+    }
+}
+
+impl Display for SaveFileError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveFileError::Io(error) => write!(f, "file access failed: {}", error),
+            SaveFileError::Format(error) => write!(f, "invalid file: {}", error),
+            SaveFileError::Version { found, expected } => write!(
+                f,
+                "file version {} is not supported, expected {}",
+                found, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SaveFileError {}
@@ -0,0 +1,88 @@
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use crate::resources::CameraDrag;
+
+const MIN_ZOOM: f32 = 0.1;
+const MAX_ZOOM: f32 = 4.0;
+/// Scale factor applied per mouse wheel line
+const ZOOM_STEP: f32 = 1.1;
+/// Pixels of a pixel scrolling mouse wheel worth one line
+const PIXELS_PER_LINE: f32 = 100.0;
+
+/// Pans the 2D camera while the left mouse button is dragged
+pub fn pan_camera(
+    window_primary_query: Query<&Window, With<PrimaryWindow>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    mut camera_drag: ResMut<CameraDrag>,
+    mut camera_query: Query<(&mut Transform, &OrthographicProjection), With<Camera2d>>,
+) {
+    if !mouse_buttons.pressed(MouseButton::Left) {
+        camera_drag.release();
+        return;
+    }
+    let Ok(window) = window_primary_query.get_single() else { return };
+    let Some(cursor) = window.cursor_position() else { return };
+    if mouse_buttons.just_pressed(MouseButton::Left) {
+        camera_drag.press(cursor);
+        return;
+    }
+    let Some(delta) = camera_drag.drag_to(cursor) else { return };
+    for (mut transform, projection) in camera_query.iter_mut() {
+        // Window y goes down, world y goes up
+        transform.translation.x -= delta.x * projection.scale;
+        transform.translation.y += delta.y * projection.scale;
+    }
+}
+
+/// Zooms the 2D camera with the mouse wheel, keeping the point under the cursor in place
+pub fn zoom_camera(
+    window_primary_query: Query<&Window, With<PrimaryWindow>>,
+    mut wheel_event_reader: EventReader<MouseWheel>,
+    mut camera_query: Query<(&mut Transform, &mut OrthographicProjection), With<Camera2d>>,
+) {
+    let lines: f32 = wheel_event_reader
+        .read()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / PIXELS_PER_LINE,
+        })
+        .sum();
+    if lines == 0.0 {
+        return;
+    }
+    let Ok(window) = window_primary_query.get_single() else { return };
+    // Cursor offset from the window center, in world orientation
+    let offset = window
+        .cursor_position()
+        .map(|cursor| (cursor - window.size() / 2.0) * Vec2::new(1.0, -1.0))
+        .unwrap_or_default();
+    for (mut transform, mut projection) in camera_query.iter_mut() {
+        let scale = (projection.scale * ZOOM_STEP.powf(-lines)).clamp(MIN_ZOOM, MAX_ZOOM);
+        let shift = offset * (projection.scale - scale);
+        transform.translation.x += shift.x;
+        transform.translation.y += shift.y;
+        projection.scale = scale;
+    }
+}
+
+/// Puts the 2D camera back on the board center at its default zoom when Home is released
+pub fn reset_camera_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    camera_query: Query<(&mut Transform, &mut OrthographicProjection), With<Camera2d>>,
+) {
+    if keys.just_released(KeyCode::Home) {
+        reset_camera(camera_query);
+    }
+}
+
+/// Puts the 2D camera back on the board center at its default zoom
+pub fn reset_camera(
+    mut camera_query: Query<(&mut Transform, &mut OrthographicProjection), With<Camera2d>>,
+) {
+    for (mut transform, mut projection) in camera_query.iter_mut() {
+        transform.translation.x = 0.0;
+        transform.translation.y = 0.0;
+        projection.scale = 1.0;
+    }
+}
@@ -0,0 +1,139 @@
+use bevy::color::palettes::tailwind;
+use bevy::prelude::*;
+use crate::components::CursorHighlight;
+use crate::events::{TileChordEvent, TileMarkEvent, TileTriggerEvent};
+use crate::resources::{Board, BoardCursor};
+
+/// Highlight frame thickness, relative to the tile size
+const FRAME_WIDTH: f32 = 0.12;
+/// Above the tiles, their covers and flags, below the pause and end covers
+const CURSOR_Z: f32 = 10.0;
+
+const LEFT_KEYS: [KeyCode; 3] = [KeyCode::ArrowLeft, KeyCode::KeyA, KeyCode::KeyH];
+const RIGHT_KEYS: [KeyCode; 3] = [KeyCode::ArrowRight, KeyCode::KeyD, KeyCode::KeyL];
+const UP_KEYS: [KeyCode; 3] = [KeyCode::ArrowUp, KeyCode::KeyW, KeyCode::KeyK];
+const DOWN_KEYS: [KeyCode; 3] = [KeyCode::ArrowDown, KeyCode::KeyS, KeyCode::KeyJ];
+
+/// Moves the cursor with the arrow keys, WASD or hjkl
+pub fn move_cursor(
+    keys: Res<ButtonInput<KeyCode>>,
+    board: Res<Board>,
+    mut board_cursor: ResMut<BoardCursor>,
+) {
+    let mut delta = (0i32, 0i32);
+    if keys.any_just_pressed(LEFT_KEYS) {
+        delta.0 -= 1;
+    }
+    if keys.any_just_pressed(RIGHT_KEYS) {
+        delta.0 += 1;
+    }
+    if keys.any_just_pressed(UP_KEYS) {
+        delta.1 += 1;
+    }
+    if keys.any_just_pressed(DOWN_KEYS) {
+        delta.1 -= 1;
+    }
+    if delta == (0, 0) {
+        return;
+    }
+    // Undo and redo use Ctrl+Z and Ctrl+Y, don't move on other Ctrl combinations either
+    if keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return;
+    }
+    board_cursor.move_by(delta, board.game.tile_map());
+}
+
+/// Reveals the cursor tile with Space or Enter, flags it with F and chords it with C
+pub fn cursor_actions(
+    keys: Res<ButtonInput<KeyCode>>,
+    board_cursor: Res<BoardCursor>,
+    mut tile_trigger_event_writer: EventWriter<TileTriggerEvent>,
+    mut tile_mark_event_writer: EventWriter<TileMarkEvent>,
+    mut tile_chord_event_writer: EventWriter<TileChordEvent>,
+) {
+    if !board_cursor.visible {
+        return;
+    }
+    let coordinates = board_cursor.coordinates;
+    if keys.any_just_pressed([KeyCode::Space, KeyCode::Enter, KeyCode::NumpadEnter]) {
+        tile_trigger_event_writer.send(TileTriggerEvent { coordinates });
+    }
+    if keys.just_pressed(KeyCode::KeyF) {
+        tile_mark_event_writer.send(TileMarkEvent { coordinates });
+    }
+    if keys.just_pressed(KeyCode::KeyC) {
+        tile_chord_event_writer.send(TileChordEvent { coordinates });
+    }
+}
+
+/// Places the highlight frame on the cursor tile, spawning it on new boards
+pub fn update_cursor_highlight(
+    mut commands: Commands,
+    board: Res<Board>,
+    mut board_cursor: ResMut<BoardCursor>,
+    mut highlight_query: Query<(&mut Transform, &mut Visibility), With<CursorHighlight>>,
+) {
+    let tile_map = board.game.tile_map();
+    let coordinates = board_cursor.coordinates;
+    // Keeps the cursor on the board when a smaller or masked one replaced it
+    if coordinates.coord_x >= tile_map.width()
+        || coordinates.coord_y >= tile_map.height()
+        || tile_map.is_hole(coordinates)
+    {
+        board_cursor.coordinates = tile_map.coordinates().next().unwrap_or_default();
+    }
+    // Tiles are laid out at the layout tile size, the board entity scale does the rest
+    let translation = board.tile_translation(board_cursor.coordinates).extend(CURSOR_Z);
+    let visibility = if board_cursor.visible {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+
+    let Ok((mut transform, mut highlight_visibility)) = highlight_query.get_single_mut() else {
+        commands.entity(board.entity).with_children(|parent| {
+            spawn_highlight(parent, board.tile_sprite_size(), translation, visibility);
+        });
+        return;
+    };
+    if transform.translation != translation {
+        transform.translation = translation;
+    }
+    if *highlight_visibility != visibility {
+        *highlight_visibility = visibility;
+    }
+}
+
+/// Four bars framing a tile of `size`, leaving its content visible
+fn spawn_highlight(parent: &mut ChildBuilder, size: Vec2, translation: Vec3, visibility: Visibility) {
+    let color = Color::from(tailwind::YELLOW_400);
+    let width = size.x * FRAME_WIDTH;
+    let offset = (size - width) / 2.0;
+    let bars = [
+        (Vec2::new(size.x, width), Vec2::new(0.0, offset.y)),
+        (Vec2::new(size.x, width), Vec2::new(0.0, -offset.y)),
+        (Vec2::new(width, size.y), Vec2::new(offset.x, 0.0)),
+        (Vec2::new(width, size.y), Vec2::new(-offset.x, 0.0)),
+    ];
+    parent
+        .spawn(SpatialBundle {
+            transform: Transform::from_translation(translation),
+            visibility,
+            ..Default::default()
+        })
+        .insert(Name::new("Cursor"))
+        .insert(CursorHighlight)
+        .with_children(|parent| {
+            for (size, position) in bars {
+                parent.spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color,
+                        custom_size: Some(size),
+                        ..Default::default()
+                    },
+                    transform: Transform::from_translation(position.extend(0.0)),
+                    ..Default::default()
+                });
+            }
+        });
+}
@@ -0,0 +1,108 @@
+use std::time::Duration;
+use bevy::prelude::*;
+use crate::events::{TileChordEvent, TileMarkEvent, TileTriggerEvent};
+use crate::resources::{Board, BoardCursor};
+
+/// Stick tilt moving the cursor
+const STICK_THRESHOLD: f32 = 0.5;
+/// Delay before a held stick moves the cursor again
+const STICK_REPEAT_DELAY: Duration = Duration::from_millis(300);
+/// Delay between moves once a held stick repeats
+const STICK_REPEAT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Direction the stick is held in and when it moves the cursor next
+#[derive(Default)]
+pub struct StickRepeat {
+    direction: (i32, i32),
+    timer: Timer,
+}
+
+/// Moves the cursor with the D-pad or the left stick of any gamepad, repeating while the stick is held
+pub fn gamepad_cursor(
+    time: Res<Time>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    board: Res<Board>,
+    mut board_cursor: ResMut<BoardCursor>,
+    mut stick_repeat: Local<StickRepeat>,
+) {
+    let tile_map = board.game.tile_map();
+    for gamepad in gamepads.iter() {
+        let just_pressed = |button_type| {
+            gamepad_buttons.just_pressed(GamepadButton::new(gamepad, button_type))
+        };
+        let delta = (
+            just_pressed(GamepadButtonType::DPadRight) as i32
+                - just_pressed(GamepadButtonType::DPadLeft) as i32,
+            just_pressed(GamepadButtonType::DPadUp) as i32
+                - just_pressed(GamepadButtonType::DPadDown) as i32,
+        );
+        if delta != (0, 0) {
+            board_cursor.move_by(delta, tile_map);
+        }
+    }
+
+    let direction = gamepads
+        .iter()
+        .map(|gamepad| {
+            let axis = |axis_type| {
+                let value = gamepad_axes
+                    .get(GamepadAxis::new(gamepad, axis_type))
+                    .unwrap_or_default();
+                if value.abs() < STICK_THRESHOLD {
+                    0
+                } else {
+                    value.signum() as i32
+                }
+            };
+            (axis(GamepadAxisType::LeftStickX), axis(GamepadAxisType::LeftStickY))
+        })
+        .find(|direction| *direction != (0, 0))
+        .unwrap_or_default();
+    if direction == (0, 0) {
+        stick_repeat.direction = direction;
+        return;
+    }
+    if direction != stick_repeat.direction {
+        stick_repeat.direction = direction;
+        stick_repeat.timer = Timer::new(STICK_REPEAT_DELAY, TimerMode::Once);
+        board_cursor.move_by(direction, tile_map);
+        return;
+    }
+    if stick_repeat.timer.tick(time.delta()).finished() {
+        stick_repeat.timer = Timer::new(STICK_REPEAT_INTERVAL, TimerMode::Once);
+        board_cursor.move_by(direction, tile_map);
+    }
+}
+
+/// South face button reveals the cursor tile, east flags it and west chords it
+pub fn gamepad_actions(
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    mut board_cursor: ResMut<BoardCursor>,
+    mut tile_trigger_event_writer: EventWriter<TileTriggerEvent>,
+    mut tile_mark_event_writer: EventWriter<TileMarkEvent>,
+    mut tile_chord_event_writer: EventWriter<TileChordEvent>,
+) {
+    for gamepad in gamepads.iter() {
+        let just_pressed = |button_type| {
+            gamepad_buttons.just_pressed(GamepadButton::new(gamepad, button_type))
+        };
+        let coordinates = board_cursor.coordinates;
+        let mut acted = true;
+        if just_pressed(GamepadButtonType::South) {
+            tile_trigger_event_writer.send(TileTriggerEvent { coordinates });
+        } else if just_pressed(GamepadButtonType::East) {
+            tile_mark_event_writer.send(TileMarkEvent { coordinates });
+        } else if just_pressed(GamepadButtonType::West) {
+            tile_chord_event_writer.send(TileChordEvent { coordinates });
+        } else {
+            acted = false;
+        }
+        // Acting shows where the action happens
+        if acted && !board_cursor.visible {
+            board_cursor.visible = true;
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use bevy::color::palettes::tailwind;
+use bevy::prelude::*;
+use minesweeper_core::solver::{Hint, Solver};
+use minesweeper_core::GameStatus;
+use crate::components::HintMarker;
+use crate::events::{HistoryEvent, TileChordEvent, TileMarkEvent, TileTriggerEvent};
+use crate::resources::{Board, BoardAssets};
+use crate::TILE_COVER_Z;
+
+/// Highlights a tile proven safe, or else a bomb left to flag, when the ? key is released
+pub fn show_hint(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut board: ResMut<Board>,
+    board_assets: Res<BoardAssets>,
+    hint_marker_query: Query<Entity, With<HintMarker>>,
+) {
+    if !keys.just_released(KeyCode::Slash) {
+        return;
+    }
+    for entity in hint_marker_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    // Nothing can be deduced before the bombs are placed
+    if !board.bombs_placed || board.game.status() != GameStatus::InProgress {
+        return;
+    }
+    let Some(hint) = Solver::hint(&board.game) else {
+        info!("No tile can be deduced, a guess is needed");
+        return;
+    };
+    let Some(tile_entity) = board.tiles.get(&hint.coordinates()).copied() else { return };
+    board.assisted = true;
+
+    let color = match hint {
+        Hint::Safe(_) => Color::from(tailwind::GREEN_400),
+        Hint::Bomb(_) => Color::from(tailwind::RED_500),
+    };
+    let size = board.tile_sprite_size();
+    let texture = board_assets.overlay_texture(board.topology());
+    commands.entity(tile_entity).with_children(|parent| {
+        parent
+            .spawn(SpriteBundle {
+                sprite: Sprite {
+                    color: color.with_alpha(0.6),
+                    custom_size: Some(size),
+                    ..Default::default()
+                },
+                texture,
+                // Above the cover and its flag
+                transform: Transform::from_xyz(0.0, 0.0, TILE_COVER_Z + 2.0),
+                ..Default::default()
+            })
+            .insert(Name::new("Hint"))
+            .insert(HintMarker);
+    });
+}
+
+/// Removes the hint once the board changes
+pub fn clear_hint(
+    mut commands: Commands,
+    hint_marker_query: Query<Entity, With<HintMarker>>,
+    mut tile_trigger_event_reader: EventReader<TileTriggerEvent>,
+    mut tile_mark_event_reader: EventReader<TileMarkEvent>,
+    mut tile_chord_event_reader: EventReader<TileChordEvent>,
+    mut history_event_reader: EventReader<HistoryEvent>,
+) {
+    let changed = tile_trigger_event_reader.read().count()
+        + tile_mark_event_reader.read().count()
+        + tile_chord_event_reader.read().count()
+        + history_event_reader.read().count()
+        > 0;
+    if !changed {
+        return;
+    }
+    for entity in hint_marker_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,54 @@
+use bevy::prelude::*;
+use bevy::state::state::FreelyMutableState;
+use minesweeper_core::GameStatus;
+use crate::events::{BoardCompletedEvent, BombExplosionEvent, HistoryEvent};
+use crate::resources::{Board, BoardAssets, BoardOptions, GameTimer};
+use crate::systems::uncover::{apply_changes, send_game_end};
+use crate::EndCoverFilter;
+
+/// Undoes or redoes the last action. Undoing the action ending the game resumes it
+#[allow(clippy::too_many_arguments)]
+pub fn history_event_handler<T: FreelyMutableState>(
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<T>>,
+    mut board: ResMut<Board>,
+    board_options: Res<BoardOptions<T>>,
+    board_assets: Res<BoardAssets>,
+    mut game_timer: ResMut<GameTimer>,
+    end_cover_query: Query<Entity, EndCoverFilter>,
+    mut history_event_reader: EventReader<HistoryEvent>,
+    mut board_completed_event_writer: EventWriter<BoardCompletedEvent>,
+    mut bomb_explosion_event_writer: EventWriter<BombExplosionEvent>,
+) {
+    // Borrowing the board mutably marks it changed, only do it when there is something to apply
+    if history_event_reader.is_empty() {
+        return;
+    }
+    let board = board.as_mut();
+    for history_event in history_event_reader.read() {
+        let changes = match history_event {
+            HistoryEvent::Undo => {
+                let Some(changes) = board.history.undo(&mut board.game) else { continue };
+                board.assisted = true;
+                changes
+            }
+            HistoryEvent::Redo => {
+                let Some(changes) = board.history.redo(&mut board.game) else { continue };
+                changes
+            }
+        };
+        apply_changes(&mut commands, board, &board_assets, &changes);
+
+        if changes.previous_status != GameStatus::InProgress && changes.status == GameStatus::InProgress {
+            for entity in end_cover_query.iter() {
+                commands.entity(entity).despawn_recursive();
+            }
+            game_timer.start();
+            next_state.set(board_options.game_state.clone());
+        }
+        // Redoing the action ending the game ends it again
+        if changes.previous_status == GameStatus::InProgress {
+            send_game_end(&changes, &mut board_completed_event_writer, &mut bomb_explosion_event_writer);
+        }
+    }
+}
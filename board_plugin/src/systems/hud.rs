@@ -0,0 +1,94 @@
+use bevy::prelude::*;
+use bevy::state::state::FreelyMutableState;
+use crate::components::{Hud, MineCounterText, TimerText};
+use minesweeper_core::ChangeSet;
+use crate::events::{BoardCompletedEvent, BombExplosionEvent};
+use crate::resources::{Board, BoardAssets, BoardOptions, GameTimer, ReplayPlayer};
+
+pub fn spawn_hud(
+    mut commands: Commands,
+    board_assets: Res<BoardAssets>,
+    hud_query: Query<(), With<Hud>>,
+) {
+    if !hud_query.is_empty() {
+        return;
+    }
+    let text_style = TextStyle {
+        font: board_assets.menu_font.clone(),
+        font_size: 24.0,
+        color: board_assets.bomb_counter_color(1),
+    };
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(0.0),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::SpaceBetween,
+                padding: UiRect::all(Val::Px(8.0)),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(Name::new("HUD"))
+        .insert(Hud)
+        .with_children(|parent| {
+            parent
+                .spawn(TextBundle::from_section("", text_style.clone()))
+                .insert(MineCounterText);
+            parent
+                .spawn(TextBundle::from_section("", text_style))
+                .insert(TimerText);
+        });
+}
+
+pub fn update_hud<T: FreelyMutableState>(
+    board: Res<Board>,
+    board_options: Res<BoardOptions<T>>,
+    game_timer: Res<GameTimer>,
+    replay_player: Option<Res<ReplayPlayer>>,
+    mut mine_counter_query: Query<&mut Text, (With<MineCounterText>, Without<TimerText>)>,
+    mut timer_query: Query<&mut Text, (With<TimerText>, Without<MineCounterText>)>,
+) {
+    // Options bomb count, the tile map has none until the first reveal when placement is deferred
+    let remaining = board_options.bomb_count as i32 - board.game.flag_count() as i32;
+    for mut text in mine_counter_query.iter_mut() {
+        text.sections[0].value = format!("Mines: {}", remaining);
+    }
+    let replay = match replay_player {
+        Some(player) if player.is_paused() => format!(" - Replay x{} paused", player.speed()),
+        Some(player) => format!(" - Replay x{}", player.speed()),
+        None => String::new(),
+    };
+    for mut text in timer_query.iter_mut() {
+        text.sections[0].value = format!("Time: {:03}{}", game_timer.elapsed().as_secs(), replay);
+    }
+}
+
+/// Starts the timer on the first reveal or chord uncovering tiles.
+/// Clicks revealing nothing, like on a flagged tile, leave it stopped
+pub fn start_timer(game_timer: &mut GameTimer, changes: &ChangeSet) {
+    if changes.uncovered.is_empty() || game_timer.is_running() || !game_timer.elapsed().is_zero() {
+        return;
+    }
+    game_timer.start();
+}
+
+/// Stops the timer once the game ended
+pub fn stop_timer(
+    mut game_timer: ResMut<GameTimer>,
+    mut bomb_explosion_event_reader: EventReader<BombExplosionEvent>,
+    mut board_completed_event_reader: EventReader<BoardCompletedEvent>,
+) {
+    if bomb_explosion_event_reader.is_empty() && board_completed_event_reader.is_empty() {
+        return;
+    }
+    bomb_explosion_event_reader.clear();
+    board_completed_event_reader.clear();
+    game_timer.stop();
+}
+
+/// Only runs in the game state, which freezes the timer while paused
+pub fn tick_timer(time: Res<Time>, mut game_timer: ResMut<GameTimer>) {
+    game_timer.tick(time.delta());
+}
\ No newline at end of file
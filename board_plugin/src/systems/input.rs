@@ -1,44 +1,110 @@
 use crate::Board;
-use crate::events::{TileMarkEvent, TileTriggerEvent};
+use crate::resources::CameraDrag;
+use crate::events::{HistoryEvent, TileChordEvent, TileMarkEvent, TileTriggerEvent};
 
+use bevy::ecs::system::SystemParam;
 use bevy::input::ButtonState;
 use bevy::input::mouse::MouseButtonInput;
 
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 
+/// Left click reveals on release, unless the press panned the camera or was part of a chord.
+/// Right click marks, middle click or pressing both left and right chords
+#[allow(clippy::too_many_arguments)]
 pub fn input_handling(
     window_primary_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
     board: Res<Board>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    camera_drag: Res<CameraDrag>,
+    mut chorded: Local<bool>,
     mut button_event_reader: EventReader<MouseButtonInput>,
     mut tile_trigger_event_writer: EventWriter<TileTriggerEvent>,
     mut tile_mark_event_writer: EventWriter<TileMarkEvent>,
+    mut tile_chord_event_writer: EventWriter<TileChordEvent>,
 ) {
    let Ok(window) = window_primary_query.get_single() else { return };
+   let Ok((camera, camera_transform)) = camera_query.get_single() else { return };
+   let tile_coordinates = window
+       .cursor_position()
+       .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor))
+       .and_then(|position| board.tile_at(position));
 
    for event in button_event_reader.read() {
-       if let ButtonState::Pressed = event.state {
-           if let Some(click_position) = window.cursor_position() {
-               if let Some(tile_coordinates) = board.mouse_position(window, click_position) {
-                   match event.button  {
-                       MouseButton::Left => {
-                           #[cfg(feature = "debug")]
-                           info!("Trying uncover tile on {}", tile_coordinates);
-                           tile_trigger_event_writer.send(TileTriggerEvent {
-                               coordinates: tile_coordinates
-                           });
-                       },
-                       MouseButton::Right => {
-                           #[cfg(feature = "debug")]
-                           info!("Trying mark tile on {}", tile_coordinates);
-                           tile_mark_event_writer.send(TileMarkEvent {
-                               coordinates: tile_coordinates
-                           });
-                       },
-                       _ => (),
-                   }
+       match event.state {
+           ButtonState::Pressed => {
+               let Some(tile_coordinates) = tile_coordinates else { continue };
+               // Middle click, or pressing one of left and right while holding the other, chords
+               let chord = match event.button {
+                   MouseButton::Middle => true,
+                   MouseButton::Left => mouse_buttons.pressed(MouseButton::Right),
+                   MouseButton::Right => mouse_buttons.pressed(MouseButton::Left),
+                   _ => false,
+               };
+               if chord {
+                   #[cfg(feature = "debug")]
+                   info!("Trying chord tile on {}", tile_coordinates);
+                   *chorded = true;
+                   tile_chord_event_writer.send(TileChordEvent {
+                       coordinates: tile_coordinates
+                   });
+               } else if event.button == MouseButton::Right {
+                   #[cfg(feature = "debug")]
+                   info!("Trying mark tile on {}", tile_coordinates);
+                   tile_mark_event_writer.send(TileMarkEvent {
+                       coordinates: tile_coordinates
+                   });
                }
-           };
+           }
+           ButtonState::Released => {
+               let reveal = event.button == MouseButton::Left && !*chorded && !camera_drag.is_dragging();
+               if !mouse_buttons.any_pressed([MouseButton::Left, MouseButton::Right]) {
+                   *chorded = false;
+               }
+               let Some(tile_coordinates) = tile_coordinates else { continue };
+               if reveal {
+                   #[cfg(feature = "debug")]
+                   info!("Trying uncover tile on {}", tile_coordinates);
+                   tile_trigger_event_writer.send(TileTriggerEvent {
+                       coordinates: tile_coordinates
+                   });
+               }
+           }
        }
    }
-}
\ No newline at end of file
+}
+
+/// Ctrl+Z undoes the last action, Ctrl+Y or Ctrl+Shift+Z redoes it
+pub fn history_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut history_event_writer: EventWriter<HistoryEvent>,
+) {
+    if !keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return;
+    }
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if keys.just_pressed(KeyCode::KeyZ) && !shift {
+        history_event_writer.send(HistoryEvent::Undo);
+    } else if keys.just_pressed(KeyCode::KeyY) || (keys.just_pressed(KeyCode::KeyZ) && shift) {
+        history_event_writer.send(HistoryEvent::Redo);
+    }
+}
+
+/// Pause toggle input: the P key or the start button of any gamepad
+#[derive(SystemParam)]
+pub struct PauseInput<'w> {
+    keys: Res<'w, ButtonInput<KeyCode>>,
+    gamepads: Res<'w, Gamepads>,
+    gamepad_buttons: Res<'w, ButtonInput<GamepadButton>>,
+}
+
+impl PauseInput<'_> {
+    pub fn just_released(&self) -> bool {
+        self.keys.just_released(KeyCode::KeyP)
+            || self.gamepads.iter().any(|gamepad| {
+                self.gamepad_buttons
+                    .just_released(GamepadButton::new(gamepad, GamepadButtonType::Start))
+            })
+    }
+}
@@ -1,46 +1,80 @@
 use bevy::prelude::*;
-use bevy::sprite::SpriteBundle;
+use bevy::sprite::{Anchor, SpriteBundle};
+use bevy::state::state::FreelyMutableState;
 use crate::events::TileMarkEvent;
-use crate::resources::{Board, BoardAssets};
-use crate::TILE_Z;
+use crate::resources::{Board, BoardAssets, BoardOptions, SpriteMaterial};
+use crate::systems::uncover::apply_changes;
+use crate::{TILE_INFO_Z, TILE_Z};
 
-pub fn mark_tiles(
+pub fn mark_tiles<T: FreelyMutableState>(
     mut commands: Commands,
     mut board: ResMut<Board>,
     board_assets: Res<BoardAssets>,
+    board_options: Res<BoardOptions<T>>,
     mut tile_mark_event_reader: EventReader<TileMarkEvent>,
-    query: Query<&Children>,
 ) {
     for tile_mark_event in tile_mark_event_reader.read() {
-        if let Some((entity, mark)) = board.try_toggle_mark(&tile_mark_event.coordinates) {
-            if mark {
-                commands.entity(entity).with_children(|parent| {
-                    parent
-                        .spawn(SpriteBundle {
-                            texture: board_assets.flag_material.texture.clone(),
-                            sprite: Sprite {
-                                custom_size: Some(Vec2::splat(board.tile_size)),
-                                color: board_assets.tile_material.color,
-                                ..Default::default()
-                            },
-                            transform: Transform::from_xyz(0., 0., TILE_Z),
-                            ..Default::default()
-                        })
-                        .insert(Name::new("Flag"));
-                });
-            } else {
-                let children = match query.get(entity) {
-                    Ok(c) => c,
-                    Err(e) => {
-                        #[cfg(feature = "debug")]
-                        error!("Failied to retrieve flag entity components: {}", e);
-                        continue;
-                    }
-                };
-                for child in children.iter() {
-                    commands.entity(*child).despawn_recursive();
-                }
-            }
-        }
+        let changes = board
+            .game
+            .cycle_mark(tile_mark_event.coordinates, board_options.question_marks);
+        board.history.record(&changes);
+        apply_changes(&mut commands, &mut board, &board_assets, &changes);
     }
-}
\ No newline at end of file
+}
+
+/// Spawns a flag sprite on the cover `entity`, with the flagged bomb quantity when above one
+pub fn spawn_flag(commands: &mut Commands, entity: Entity, board_assets: &BoardAssets, tile_size: f32, count: u8) {
+    spawn_mark(commands, entity, board_assets, &board_assets.flag_material, "Flag", tile_size);
+    if count > 1 {
+        commands.entity(entity).with_children(|parent| {
+            parent
+                .spawn(quantity_text_bundle(count, board_assets, tile_size))
+                .insert(Name::new("Flag count"));
+        });
+    }
+}
+
+/// Spawns a question mark sprite on the cover `entity`
+pub fn spawn_question_mark(commands: &mut Commands, entity: Entity, board_assets: &BoardAssets, tile_size: f32) {
+    spawn_mark(commands, entity, board_assets, &board_assets.question_material, "Question mark", tile_size);
+}
+
+/// Bomb quantity in the bottom right corner of a tile, on boards where tiles hold several bombs
+pub fn quantity_text_bundle(count: u8, board_assets: &BoardAssets, tile_size: f32) -> Text2dBundle {
+    let style = TextStyle {
+        font: board_assets.bomb_counter_font.clone(),
+        font_size: tile_size / 2.0,
+        color: board_assets.bomb_counter_color(count),
+    };
+    Text2dBundle {
+        text: Text::from_section(count.to_string(), style),
+        text_anchor: Anchor::BottomRight,
+        // Above the flag and bomb sprites
+        transform: Transform::from_xyz(tile_size / 2.0, -tile_size / 2.0, TILE_INFO_Z + 0.5),
+        ..Default::default()
+    }
+}
+
+fn spawn_mark(
+    commands: &mut Commands,
+    entity: Entity,
+    board_assets: &BoardAssets,
+    material: &SpriteMaterial,
+    name: &'static str,
+    tile_size: f32,
+) {
+    commands.entity(entity).with_children(|parent| {
+        parent
+            .spawn(SpriteBundle {
+                texture: material.texture.clone(),
+                sprite: Sprite {
+                    custom_size: Some(Vec2::splat(tile_size)),
+                    color: board_assets.tile_material.color,
+                    ..Default::default()
+                },
+                transform: Transform::from_xyz(0., 0., TILE_Z),
+                ..Default::default()
+            })
+            .insert(Name::new(name));
+    });
+}
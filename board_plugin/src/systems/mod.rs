@@ -1,3 +1,11 @@
 pub mod input;
 pub mod uncover;
-pub mod mark;
\ No newline at end of file
+pub mod mark;
+pub mod hud;
+pub mod stats;
+pub mod history;pub mod replay;
+pub mod hint;
+pub mod probability;
+pub mod cursor;
+pub mod gamepad;
+pub mod camera;
@@ -0,0 +1,87 @@
+use bevy::color::palettes::tailwind;
+use bevy::prelude::*;
+use minesweeper_core::probability::mine_probabilities;
+use minesweeper_core::GameStatus;
+use crate::components::ProbabilityMarker;
+use crate::resources::{Board, BoardAssets, ProbabilityOverlay};
+
+/// Toggles the mine probability overlay when O is released
+pub fn toggle_probability_overlay(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut probability_overlay: ResMut<ProbabilityOverlay>,
+) {
+    if keys.just_released(KeyCode::KeyO) {
+        probability_overlay.enabled = !probability_overlay.enabled;
+    }
+}
+
+/// Tints every tile cover from green to red by its mine probability, and writes it in percents
+pub fn update_probability_overlay(
+    mut commands: Commands,
+    mut board: ResMut<Board>,
+    board_assets: Res<BoardAssets>,
+    probability_overlay: Res<ProbabilityOverlay>,
+    marker_query: Query<Entity, With<ProbabilityMarker>>,
+    mut shown_revision: Local<Option<(Entity, u64)>>,
+) {
+    // Board entity and revision the markers were computed for
+    let revision = Some((board.entity, board.revision));
+    if *shown_revision == revision && !probability_overlay.is_changed() {
+        return;
+    }
+    *shown_revision = revision;
+    for entity in marker_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    // Nothing is known before the bombs are placed
+    if !probability_overlay.enabled
+        || !board.bombs_placed
+        || board.game.status() != GameStatus::InProgress
+    {
+        return;
+    }
+    let Some(probabilities) = mine_probabilities(&board.game) else {
+        info!("Mine probabilities can't be computed on this board");
+        return;
+    };
+    board.assisted = true;
+
+    let size = board.tile_sprite_size();
+    let texture = board_assets.overlay_texture(board.topology());
+    let text_style = TextStyle {
+        font: board_assets.bomb_counter_font.clone(),
+        font_size: size.x / 3.0,
+        color: Color::from(tailwind::NEUTRAL_50),
+    };
+    for (coordinates, probability) in probabilities {
+        let Some(cover) = board.covered_tiles.get(&coordinates) else { continue };
+        let color = tailwind::GREEN_500.mix(&tailwind::RED_600, probability as f32);
+        commands.entity(*cover).with_children(|parent| {
+            parent
+                .spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::from(color).with_alpha(0.5),
+                        custom_size: Some(size),
+                        ..Default::default()
+                    },
+                    texture: texture.clone(),
+                    // Between the cover and its flag
+                    transform: Transform::from_xyz(0.0, 0.0, 0.5),
+                    ..Default::default()
+                })
+                .insert(Name::new("Mine probability"))
+                .insert(ProbabilityMarker);
+            parent
+                .spawn(Text2dBundle {
+                    text: Text::from_section(
+                        format!("{:.0}", probability * 100.0),
+                        text_style.clone(),
+                    )
+                    .with_justify(JustifyText::Center),
+                    transform: Transform::from_xyz(0.0, 0.0, 2.0),
+                    ..Default::default()
+                })
+                .insert(ProbabilityMarker);
+        });
+    }
+}
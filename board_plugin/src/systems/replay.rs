@@ -0,0 +1,113 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::state::state::FreelyMutableState;
+use crate::events::{BoardCompletedEvent, BombExplosionEvent, HistoryEvent, TileChordEvent, TileMarkEvent, TileTriggerEvent};
+use crate::resources::{BoardOptions, ReplayInput, ReplayPlayer, ReplayRecorder};
+
+/// Sends recorded input as the events the player input sends
+#[derive(SystemParam)]
+pub struct ReplayInputWriter<'w> {
+    tile_trigger_event_writer: EventWriter<'w, TileTriggerEvent>,
+    tile_mark_event_writer: EventWriter<'w, TileMarkEvent>,
+    tile_chord_event_writer: EventWriter<'w, TileChordEvent>,
+    history_event_writer: EventWriter<'w, HistoryEvent>,
+}
+
+impl ReplayInputWriter<'_> {
+    fn send(&mut self, input: ReplayInput) {
+        match input {
+            ReplayInput::Reveal(coordinates) => {
+                self.tile_trigger_event_writer.send(TileTriggerEvent { coordinates });
+            }
+            ReplayInput::Mark(coordinates) => {
+                self.tile_mark_event_writer.send(TileMarkEvent { coordinates });
+            }
+            ReplayInput::Chord(coordinates) => {
+                self.tile_chord_event_writer.send(TileChordEvent { coordinates });
+            }
+            ReplayInput::Undo => {
+                self.history_event_writer.send(HistoryEvent::Undo);
+            }
+            ReplayInput::Redo => {
+                self.history_event_writer.send(HistoryEvent::Redo);
+            }
+        }
+    }
+}
+
+/// Records the player input of the current board
+pub fn record_actions(
+    time: Res<Time>,
+    mut replay_recorder: ResMut<ReplayRecorder>,
+    mut tile_trigger_event_reader: EventReader<TileTriggerEvent>,
+    mut tile_mark_event_reader: EventReader<TileMarkEvent>,
+    mut tile_chord_event_reader: EventReader<TileChordEvent>,
+    mut history_event_reader: EventReader<HistoryEvent>,
+) {
+    replay_recorder.tick(time.delta());
+    for event in tile_trigger_event_reader.read() {
+        replay_recorder.record(ReplayInput::Reveal(event.coordinates));
+    }
+    for event in tile_mark_event_reader.read() {
+        replay_recorder.record(ReplayInput::Mark(event.coordinates));
+    }
+    for event in tile_chord_event_reader.read() {
+        replay_recorder.record(ReplayInput::Chord(event.coordinates));
+    }
+    for event in history_event_reader.read() {
+        replay_recorder.record(match event {
+            HistoryEvent::Undo => ReplayInput::Undo,
+            HistoryEvent::Redo => ReplayInput::Redo,
+        });
+    }
+}
+
+/// Writes the replay of the finished game to the options `replay_path`
+pub fn save_replay<T: FreelyMutableState>(
+    board_options: Res<BoardOptions<T>>,
+    replay_recorder: Res<ReplayRecorder>,
+    mut bomb_explosion_event_reader: EventReader<BombExplosionEvent>,
+    mut board_completed_event_reader: EventReader<BoardCompletedEvent>,
+) {
+    if bomb_explosion_event_reader.is_empty() && board_completed_event_reader.is_empty() {
+        return;
+    }
+    bomb_explosion_event_reader.clear();
+    board_completed_event_reader.clear();
+    let Some(path) = board_options.replay_path.as_ref() else { return };
+    match replay_recorder.replay().save(path) {
+        Ok(()) => info!("Replay saved to {}", path.display()),
+        Err(e) => error!("Failed to save replay to {}: {}", path.display(), e),
+    }
+}
+
+/// Sends the recorded input due at the replay speed
+pub fn play_replay(
+    time: Res<Time>,
+    mut replay_player: ResMut<ReplayPlayer>,
+    mut replay_input_writer: ReplayInputWriter,
+) {
+    for input in replay_player.advance(time.delta()) {
+        replay_input_writer.send(input);
+    }
+}
+
+/// Space pauses the replay, the right arrow plays the next action, 1, 2 and 4 set the speed
+pub fn replay_controls(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut replay_player: ResMut<ReplayPlayer>,
+    mut replay_input_writer: ReplayInputWriter,
+) {
+    if keys.just_pressed(KeyCode::Space) {
+        replay_player.toggle_pause();
+    }
+    for (key, speed) in [(KeyCode::Digit1, 1.0), (KeyCode::Digit2, 2.0), (KeyCode::Digit4, 4.0)] {
+        if keys.just_pressed(key) {
+            replay_player.set_speed(speed);
+        }
+    }
+    if keys.just_pressed(KeyCode::ArrowRight) && replay_player.is_paused() {
+        let Some(input) = replay_player.step() else { return };
+        replay_input_writer.send(input);
+    }
+}
@@ -0,0 +1,163 @@
+use bevy::prelude::*;
+use bevy::state::state::FreelyMutableState;
+use minesweeper_core::GameStatus;
+use crate::events::{BoardCompletedEvent, BombExplosionEvent};
+use crate::resources::{Board, BoardOptions, GameStats, GameTimer, StatsKey};
+
+/// Loads the player statistics once the board options are known
+pub fn load_stats<T: FreelyMutableState>(
+    mut commands: Commands,
+    board_options: Res<BoardOptions<T>>,
+) {
+    let stats = match board_options.stats_path.as_ref() {
+        Some(path) => GameStats::load_or_default(path).unwrap_or_else(|e| {
+            error!("Failed to load stats from {}: {}", path.display(), e);
+            GameStats::default()
+        }),
+        None => GameStats::default(),
+    };
+    commands.insert_resource(stats);
+}
+
+/// Records the ended game in the stats of its board configuration.
+/// An end undone afterwards is retracted, the game is recorded as assisted once it ends again.
+/// Variant boards aren't recorded, the stats key can't tell them from the classic ones
+pub fn record_game_end<T: FreelyMutableState>(
+    mut board: ResMut<Board>,
+    board_options: Res<BoardOptions<T>>,
+    game_timer: Res<GameTimer>,
+    mut game_stats: ResMut<GameStats>,
+    mut bomb_explosion_event_reader: EventReader<BombExplosionEvent>,
+    mut board_completed_event_reader: EventReader<BoardCompletedEvent>,
+) {
+    let key = StatsKey {
+        columns: board_options.map_size.columns,
+        rows: board_options.map_size.rows,
+        bomb_count: board_options.bomb_count,
+    };
+    let lost = !bomb_explosion_event_reader.is_empty();
+    let won = !board_completed_event_reader.is_empty();
+    bomb_explosion_event_reader.clear();
+    board_completed_event_reader.clear();
+    if !board_options.is_classic() {
+        return;
+    }
+    if !lost && !won {
+        // Undoing the recorded end resumed the game. Checked first, taking the stats marks the board changed
+        if board.game.status() != GameStatus::InProgress || board.recorded_stats.is_none() {
+            return;
+        }
+        let Some(recorded_stats) = board.recorded_stats.take() else { return };
+        *game_stats.get_mut(key) = recorded_stats;
+    } else {
+        let stats = game_stats.get_mut(key);
+        board.recorded_stats = Some(stats.clone());
+        if board.assisted {
+            stats.record_assisted(won);
+        } else if won {
+            stats.record_win(game_timer.elapsed(), board.game.tile_map().three_bv());
+        } else {
+            stats.record_loss();
+        }
+    }
+    let Some(path) = board_options.stats_path.as_ref() else { return };
+    if let Err(e) = game_stats.save(path) {
+        error!("Failed to save stats to {}: {}", path.display(), e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use minesweeper_core::Game;
+    use minesweeper_core::tile_map::TileMap;
+    use crate::components::Coordinates;
+    use crate::resources::{seeded_std_rng, BoardTopology, ConfigurationStats, TestState};
+
+    const KEY: StatsKey = StatsKey { columns: 2, rows: 1, bomb_count: 1 };
+
+    /// App recording the game end of a 2x1 board with one bomb, and the coordinates of its bomb and safe tile
+    fn app() -> (App, Coordinates, Coordinates) {
+        let mut tile_map = TileMap::empty(2, 1);
+        tile_map.set_bombs(1, &mut *seeded_std_rng(0));
+        let left = Coordinates { coord_x: 0, coord_y: 0 };
+        let right = Coordinates { coord_x: 1, coord_y: 0 };
+        let (bomb, safe) = if tile_map.is_bomb_at(left) { (left, right) } else { (right, left) };
+        let mut app = App::new();
+        app.add_event::<BombExplosionEvent>()
+            .add_event::<BoardCompletedEvent>()
+            .insert_resource(Board::detached(Game::new(tile_map)))
+            .insert_resource(BoardOptions::test(2, 1, 1))
+            .insert_resource(GameTimer::resumed(Duration::from_secs(5)))
+            .init_resource::<GameStats>()
+            .add_systems(Update, record_game_end::<TestState>);
+        (app, bomb, safe)
+    }
+
+    fn reveal(app: &mut App, coordinates: Coordinates) {
+        let mut board = app.world_mut().resource_mut::<Board>();
+        let board = board.as_mut();
+        let changes = board.game.reveal(coordinates);
+        board.history.record(&changes);
+    }
+
+    fn undo(app: &mut App) {
+        let mut board = app.world_mut().resource_mut::<Board>();
+        let board = board.as_mut();
+        board.history.undo(&mut board.game);
+        board.assisted = true;
+    }
+
+    fn stats(app: &App) -> ConfigurationStats {
+        app.world().resource::<GameStats>().get(&KEY).cloned().unwrap_or_default()
+    }
+
+    #[test]
+    fn undone_wins_are_retracted() {
+        let (mut app, _, safe) = app();
+        app.world_mut().resource_mut::<GameStats>().get_mut(KEY).record_win(Duration::from_secs(9), 1);
+        let before = stats(&app);
+        reveal(&mut app, safe);
+        app.world_mut().send_event(BoardCompletedEvent);
+        app.update();
+        assert_eq!(stats(&app).games_won, 2);
+        assert_eq!(stats(&app).best_time, Some(Duration::from_secs(5)));
+
+        undo(&mut app);
+        app.update();
+        assert_eq!(stats(&app), before);
+
+        // Winning again after undoing is an assisted win
+        reveal(&mut app, safe);
+        app.world_mut().send_event(BoardCompletedEvent);
+        app.update();
+        assert_eq!(stats(&app).games_won, 1);
+        assert_eq!(stats(&app).assisted_wins, 1);
+    }
+
+    #[test]
+    fn undone_losses_are_retracted() {
+        let (mut app, bomb, _) = app();
+        app.world_mut().resource_mut::<GameStats>().get_mut(KEY).record_win(Duration::from_secs(9), 1);
+        let before = stats(&app);
+        reveal(&mut app, bomb);
+        app.world_mut().send_event(BombExplosionEvent);
+        app.update();
+        assert_eq!(stats(&app).current_streak, 0);
+
+        undo(&mut app);
+        app.update();
+        assert_eq!(stats(&app), before);
+    }
+
+    #[test]
+    fn variant_boards_are_not_recorded() {
+        let (mut app, _, safe) = app();
+        app.world_mut().resource_mut::<BoardOptions<TestState>>().topology = BoardTopology::Hex;
+        reveal(&mut app, safe);
+        app.world_mut().send_event(BoardCompletedEvent);
+        app.update();
+        assert_eq!(app.world().resource::<GameStats>().get(&KEY), None);
+    }
+}
@@ -1,76 +1,158 @@
+#[cfg(feature = "debug")]
 use bevy::log::*;
-use bevy::prelude::{Commands, DespawnRecursiveExt, Entity, EventReader, EventWriter, Parent, Query, Res, ResMut, With};
-use crate::components::{Bomb, BombNeighbor, Coordinates, Uncover};
-use crate::events::{BoardCompletedEvent, BombExplosionEvent, TileTriggerEvent};
-use crate::resources::Board;
+use bevy::prelude::*;
+use minesweeper_core::{ChangeSet, GameStatus};
+use crate::events::{BoardCompletedEvent, BombExplosionEvent, TileChordEvent, TileTriggerEvent};
+use crate::resources::{Board, BoardAssets, BoardTopology, GameTimer};
+use crate::systems::hud::start_timer;
+use crate::systems::mark::{spawn_flag, spawn_question_mark};
+use crate::TILE_COVER_Z;
 
 pub fn trigger_event_handler(
     mut commands: Commands,
-    board: Res<Board>,
-    mut tile_trigger_event_reader: EventReader<TileTriggerEvent>
+    mut board: ResMut<Board>,
+    board_assets: Res<BoardAssets>,
+    mut game_timer: ResMut<GameTimer>,
+    mut tile_trigger_event_reader: EventReader<TileTriggerEvent>,
+    mut board_completed_event_writer: EventWriter<BoardCompletedEvent>,
+    mut bomb_explosion_event_writer: EventWriter<BombExplosionEvent>,
 ) {
     for trigger_event in tile_trigger_event_reader.read() {
         #[cfg(feature = "debug")]
         info!("Tile trigger event handler {:?}", trigger_event);
-        if let Some(entity) = board.tile_to_uncover(&trigger_event.coordinates) {
-            #[cfg(feature = "debug")]
-            info!("Insert uncover to {:?}", entity);
-            commands.entity(*entity).insert(Uncover);
-        }
+        let changes = board.game.reveal(trigger_event.coordinates);
+        start_timer(&mut game_timer, &changes);
+        board.history.record(&changes);
+        apply_changes(&mut commands, &mut board, &board_assets, &changes);
+        send_game_end(&changes, &mut board_completed_event_writer, &mut bomb_explosion_event_writer);
     }
 }
 
-pub fn uncover_tiles(
+pub fn chord_event_handler(
     mut commands: Commands,
     mut board: ResMut<Board>,
-    children: Query<(Entity, &Parent), With<Uncover>>,
-    parents: Query<(&Coordinates, Option<&Bomb>, Option<&BombNeighbor>)>,
+    board_assets: Res<BoardAssets>,
+    mut game_timer: ResMut<GameTimer>,
+    mut tile_chord_event_reader: EventReader<TileChordEvent>,
     mut board_completed_event_writer: EventWriter<BoardCompletedEvent>,
     mut bomb_explosion_event_writer: EventWriter<BombExplosionEvent>,
 ) {
-    // Iterate through tile covers to uncover
-    for (entity, parent) in children.iter() {
-        // Destroy the tile cover entity
-       commands.entity(entity).despawn_recursive();
-
-        let (coordinates, bomb, bomb_counter) = match parents.get(parent.get()) {
-            Ok(v) => v,
-            Err(e) => {
-                error!("{}", e);
-                continue;
-            }
-        };
+    for chord_event in tile_chord_event_reader.read() {
+        #[cfg(feature = "debug")]
+        info!("Tile chord event handler {:?}", chord_event);
+        let changes = board.game.chord(chord_event.coordinates);
+        start_timer(&mut game_timer, &changes);
+        board.history.record(&changes);
+        apply_changes(&mut commands, &mut board, &board_assets, &changes);
+        send_game_end(&changes, &mut board_completed_event_writer, &mut bomb_explosion_event_writer);
+    }
+}
 
-        match board.try_uncover_tile(coordinates) {
+/// Renders the outcome of a game action: despawns uncovered tile covers, spawns covers back
+/// and adds or removes flags and question marks, updating the flagged bomb quantities
+pub fn apply_changes(
+    commands: &mut Commands,
+    board: &mut Board,
+    board_assets: &BoardAssets,
+    changes: &ChangeSet,
+) {
+    // Tiles are spawned at the layout tile size, the board entity scale does the rest
+    if !changes.is_empty() {
+        board.revision += 1;
+    }
+    let tile_size = board.layout_tile_size;
+    let topology = board.topology();
+    let cover_size = board.tile_sprite_size();
+    for coordinates in &changes.covered {
+        let Some(tile_entity) = board.tiles.get(coordinates).copied() else { continue };
+        commands.entity(tile_entity).with_children(|parent| {
+            let entity = spawn_cover(parent, board_assets, topology, cover_size);
+            board.covered_tiles.insert(*coordinates, entity);
+        });
+    }
+    for coordinates in changes.unflagged.iter().chain(&changes.unquestioned) {
+        if let Some(entity) = board.covered_tiles.get(coordinates) {
+            commands.entity(*entity).despawn_descendants();
+        }
+    }
+    for coordinates in &changes.uncovered {
+        match board.covered_tiles.remove(coordinates) {
             None => {
                 #[cfg(feature = "debug")]
                 info!("Tried to uncover an already uncovered tile")
             },
-            Some(_e) => {
+            Some(entity) => {
                 #[cfg(feature = "debug")]
-                info!("Uncovered tile {} (entity: {:?})",coordinates, _e)
+                info!("Uncovered tile {} (entity: {:?})", coordinates, entity);
+                commands.entity(entity).despawn_recursive();
             },
         }
-        
-        if board.is_completed() {
+    }
+    for coordinates in &changes.flagged {
+        if let Some(entity) = board.covered_tiles.get(coordinates) {
+            spawn_flag(commands, *entity, board_assets, tile_size, board.game.flag_count_at(coordinates));
+        }
+    }
+    // Quantity changes of tiles staying flagged respawn their flag, unflagged ones are cleared above
+    for change in &changes.flag_counts {
+        let coordinates = &change.coordinates;
+        if changes.flagged.contains(coordinates) || !board.game.is_flagged(coordinates) {
+            continue;
+        }
+        if let Some(entity) = board.covered_tiles.get(coordinates) {
+            commands.entity(*entity).despawn_descendants();
+            spawn_flag(commands, *entity, board_assets, tile_size, board.game.flag_count_at(coordinates));
+        }
+    }
+    for coordinates in &changes.questioned {
+        if let Some(entity) = board.covered_tiles.get(coordinates) {
+            spawn_question_mark(commands, *entity, board_assets, tile_size);
+        }
+    }
+}
+
+/// Spawns a tile cover of `size` pixels under `parent`, shaped following `topology`
+pub fn spawn_cover(
+    parent: &mut ChildBuilder,
+    board_assets: &BoardAssets,
+    topology: BoardTopology,
+    size: Vec2,
+) -> Entity {
+    parent
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(size),
+                color: board_assets.covered_tile_material.color,
+                ..Default::default()
+            },
+            texture: board_assets.tile_texture(topology, &board_assets.covered_tile_material),
+            transform: Transform::from_xyz(0.0, 0.0, TILE_COVER_Z),
+            ..Default::default()
+        })
+        .insert(Name::new("Tile Cover"))
+        .id()
+}
+
+/// Reports the end of the game, if the action ended it
+pub fn send_game_end(
+    changes: &ChangeSet,
+    board_completed_event_writer: &mut EventWriter<BoardCompletedEvent>,
+    bomb_explosion_event_writer: &mut EventWriter<BombExplosionEvent>,
+) {
+    if changes.uncovered.is_empty() {
+        return;
+    }
+    match changes.status {
+        GameStatus::Won => {
             #[cfg(feature = "debug")]
             info!("Board completed!");
             board_completed_event_writer.send(BoardCompletedEvent);
         }
-
-        if bomb.is_some() {
+        GameStatus::Lost => {
             #[cfg(feature = "debug")]
             info!("Boom!");
             bomb_explosion_event_writer.send(BombExplosionEvent);
         }
-        // if the tile is empty (no bomb near tile)..
-        else if bomb_counter.is_none() {
-            // ..We propagate the uncovering by adding the 'Uncover'
-            // which will then be removed next frame
-            for entity in board.adjacent_covered_tiles(*coordinates) {
-                commands.entity(entity).insert(Uncover);
-            };
-        }
-
+        GameStatus::InProgress => (),
     }
 }
\ No newline at end of file
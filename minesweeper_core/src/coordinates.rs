@@ -1,12 +1,11 @@
 use std::fmt;
 use std::fmt::{Display, Formatter};
 use std::ops::{Add, Sub};
-use bevy::prelude::Component;
-// use bevy_inspector_egui::{prelude::*, reflect_inspector};
+use serde::{Deserialize, Serialize};
 
-// #[cfg_attr(feature = "debug", derive(InspectorOptions))]
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)] // lv - add derives on demand
-#[derive(Component)]
+/// Tile coordinates, `(0:0)` being the bottom left tile
+#[derive(Debug, Default, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)] // lv - add derives on demand
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::component::Component))]
 pub struct Coordinates {
     pub coord_y: u16,
     pub coord_x: u16,
@@ -32,6 +31,23 @@ impl Add<(i8, i8)> for Coordinates {
     }
 }
 
+impl Coordinates {
+    /// Adds `(x, y)`, wrapping around a `width` x `height` map like on a torus
+    pub fn wrapping_add(self, (x, y): (i8, i8), width: u16, height: u16) -> Self {
+        Self {
+            coord_x: (self.coord_x as i32 + x as i32).rem_euclid(width as i32) as u16,
+            coord_y: (self.coord_y as i32 + y as i32).rem_euclid(height as i32) as u16,
+        }
+    }
+}
+
+#[cfg(test)]
+impl Coordinates {
+    pub(crate) const fn at(coord_x: u16, coord_y: u16) -> Self {
+        Self { coord_x, coord_y }
+    }
+}
+
 impl Sub for Coordinates {
     type Output = Self;
 
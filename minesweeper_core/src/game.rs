@@ -0,0 +1,586 @@
+use std::collections::{BTreeMap, BTreeSet};
+use serde::{Deserialize, Serialize};
+use crate::Coordinates;
+use crate::tile::Tile;
+use crate::tile_map::TileMap;
+
+/// Progress of a game
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GameStatus {
+    /// Game can still be played
+    #[default]
+    InProgress,
+    /// Every safe tile is uncovered
+    Won,
+    /// A bomb was uncovered
+    Lost,
+}
+
+/// Flag quantity change of a tile, on boards where tiles hold several bombs
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FlagCountChange {
+    pub coordinates: Coordinates,
+    /// Quantity before the action
+    pub previous: u8,
+    /// Quantity after the action
+    pub count: u8,
+}
+
+impl FlagCountChange {
+    /// Change reverting this one
+    pub fn inverse(&self) -> FlagCountChange {
+        FlagCountChange {
+            coordinates: self.coordinates,
+            previous: self.count,
+            count: self.previous,
+        }
+    }
+}
+
+/// Tiles changed by a game action
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChangeSet {
+    /// Uncovered tiles, in uncovering order
+    pub uncovered: Vec<Coordinates>,
+    /// Newly flagged tiles
+    pub flagged: Vec<Coordinates>,
+    /// Unflagged tiles, either toggled or uncovered
+    pub unflagged: Vec<Coordinates>,
+    /// Newly question marked tiles
+    pub questioned: Vec<Coordinates>,
+    /// Tiles losing their question mark, either cycled or uncovered
+    pub unquestioned: Vec<Coordinates>,
+    /// Covered again tiles, when undoing an action
+    pub covered: Vec<Coordinates>,
+    /// Flag quantity changes, in action order
+    pub flag_counts: Vec<FlagCountChange>,
+    /// Uncovered bomb ending the game
+    pub exploded: Option<Coordinates>,
+    /// Status before the action
+    pub previous_status: GameStatus,
+    /// Status after the action
+    pub status: GameStatus,
+}
+
+impl ChangeSet {
+    /// Changes of an action on a game of the given status
+    fn from_status(status: GameStatus) -> Self {
+        Self {
+            previous_status: status,
+            status,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.uncovered.is_empty()
+            && self.flagged.is_empty()
+            && self.unflagged.is_empty()
+            && self.questioned.is_empty()
+            && self.unquestioned.is_empty()
+            && self.covered.is_empty()
+            && self.flag_counts.is_empty()
+    }
+
+    /// Changes reverting these ones
+    pub fn inverse(&self) -> ChangeSet {
+        ChangeSet {
+            uncovered: self.covered.clone(),
+            flagged: self.unflagged.clone(),
+            unflagged: self.flagged.clone(),
+            questioned: self.unquestioned.clone(),
+            unquestioned: self.questioned.clone(),
+            covered: self.uncovered.clone(),
+            flag_counts: self.flag_counts.iter().rev().map(FlagCountChange::inverse).collect(),
+            exploded: None,
+            previous_status: self.status,
+            status: self.previous_status,
+        }
+    }
+
+    /// Appends the changes of a following action
+    fn extend(&mut self, other: ChangeSet) {
+        self.uncovered.extend(other.uncovered);
+        self.flagged.extend(other.flagged);
+        self.unflagged.extend(other.unflagged);
+        self.questioned.extend(other.questioned);
+        self.unquestioned.extend(other.unquestioned);
+        self.covered.extend(other.covered);
+        self.flag_counts.extend(other.flag_counts);
+        self.exploded = self.exploded.or(other.exploded);
+        self.status = other.status;
+    }
+}
+
+/// Minesweeper rules over a tile map: covers, flags and game completion
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Game {
+    tile_map: TileMap,
+    covered: BTreeSet<Coordinates>,
+    flagged: BTreeSet<Coordinates>,
+    /// Question marks are only reminders: they don't count as flags nor block reveals
+    #[serde(default)]
+    questioned: BTreeSet<Coordinates>,
+    /// Flag quantities above one, on maps where tiles hold several bombs
+    #[serde(default)]
+    flag_counts: BTreeMap<Coordinates, u8>,
+    status: GameStatus,
+}
+
+impl Game {
+    /// Starts a game with every tile covered
+    pub fn new(tile_map: TileMap) -> Self {
+        let covered = tile_map.coordinates().collect();
+        Self {
+            tile_map,
+            covered,
+            flagged: BTreeSet::new(),
+            questioned: BTreeSet::new(),
+            flag_counts: BTreeMap::new(),
+            status: GameStatus::InProgress,
+        }
+    }
+
+    pub fn tile_map(&self) -> &TileMap {
+        &self.tile_map
+    }
+
+    pub fn status(&self) -> GameStatus {
+        self.status
+    }
+
+    /// Replaces the tile map, keeping covers and flags.
+    /// Meant to place bombs before the first reveal
+    pub fn replace_tile_map(&mut self, tile_map: TileMap) {
+        self.tile_map = tile_map;
+    }
+
+    pub fn is_covered(&self, coordinates: &Coordinates) -> bool {
+        self.covered.contains(coordinates)
+    }
+
+    pub fn is_flagged(&self, coordinates: &Coordinates) -> bool {
+        self.flagged.contains(coordinates)
+    }
+
+    pub fn is_questioned(&self, coordinates: &Coordinates) -> bool {
+        self.questioned.contains(coordinates)
+    }
+
+    pub fn covered(&self) -> impl Iterator<Item = &Coordinates> {
+        self.covered.iter()
+    }
+
+    pub fn flagged(&self) -> impl Iterator<Item = &Coordinates> {
+        self.flagged.iter()
+    }
+
+    pub fn questioned(&self) -> impl Iterator<Item = &Coordinates> {
+        self.questioned.iter()
+    }
+
+    /// Bombs marked by the flag of a tile, zero when unflagged
+    pub fn flag_count_at(&self, coordinates: &Coordinates) -> u8 {
+        if !self.is_flagged(coordinates) {
+            return 0;
+        }
+        self.flag_counts.get(coordinates).copied().unwrap_or(1)
+    }
+
+    /// Bombs marked by all the flags
+    pub fn flag_count(&self) -> usize {
+        self.flagged
+            .iter()
+            .map(|coordinates| self.flag_count_at(coordinates) as usize)
+            .sum()
+    }
+
+    /// Sets the flag quantity of a tile, recording it in `changes` when it differs
+    fn set_flag_count(&mut self, coordinates: Coordinates, count: u8, changes: &mut ChangeSet) {
+        let previous = self.flag_counts.get(&coordinates).copied().unwrap_or(1);
+        if previous == count {
+            return;
+        }
+        if count > 1 {
+            self.flag_counts.insert(coordinates, count);
+        } else {
+            self.flag_counts.remove(&coordinates);
+        }
+        changes.flag_counts.push(FlagCountChange { coordinates, previous, count });
+    }
+
+    /// Uncovers a tile, propagating through empty tiles. Flagged tiles can't be revealed
+    pub fn reveal(&mut self, coordinates: Coordinates) -> ChangeSet {
+        let mut changes = ChangeSet::from_status(self.status);
+        if self.status != GameStatus::InProgress
+            || !self.is_covered(&coordinates)
+            || self.is_flagged(&coordinates)
+        {
+            return changes;
+        }
+
+        let mut stack = vec![coordinates];
+        while let Some(coordinates) = stack.pop() {
+            if !self.covered.remove(&coordinates) {
+                continue;
+            }
+            // Propagation uncovers wrongly flagged tiles
+            if self.flagged.remove(&coordinates) {
+                self.set_flag_count(coordinates, 1, &mut changes);
+                changes.unflagged.push(coordinates);
+            }
+            if self.questioned.remove(&coordinates) {
+                changes.unquestioned.push(coordinates);
+            }
+            changes.uncovered.push(coordinates);
+            match self.tile_map.tile(coordinates) {
+                Some(Tile::Bomb(_)) => {
+                    self.status = GameStatus::Lost;
+                    changes.exploded = Some(coordinates);
+                    break;
+                }
+                Some(Tile::Empty) => stack.extend(
+                    self.tile_map
+                        .safe_square_at(coordinates)
+                        .filter(|c| self.covered.contains(c)),
+                ),
+                _ => (),
+            }
+        }
+
+        if self.status == GameStatus::InProgress && self.is_completed() {
+            self.status = GameStatus::Won;
+            // Every remaining covered tile is a bomb, flag them with their bomb quantity
+            let unflagged: Vec<Coordinates> = self
+                .covered
+                .difference(&self.flagged)
+                .copied()
+                .collect();
+            self.flagged.extend(unflagged.iter().copied());
+            changes.flagged = unflagged;
+            let covered: Vec<Coordinates> = self.covered.iter().copied().collect();
+            for coordinates in covered {
+                let count = self.tile_map.tile(coordinates).map_or(1, |tile| tile.bomb_count());
+                self.set_flag_count(coordinates, count, &mut changes);
+            }
+            changes.unquestioned.extend(std::mem::take(&mut self.questioned));
+        }
+        changes.status = self.status;
+        changes
+    }
+
+    /// Flags or unflags a covered tile
+    pub fn toggle_flag(&mut self, coordinates: Coordinates) -> ChangeSet {
+        self.cycle_mark(coordinates, false)
+    }
+
+    /// Cycles the mark of a covered tile: none, flag, then question mark if `question_marks`
+    /// is set, back to none. On maps where tiles hold several bombs, the flag counts up to
+    /// that quantity before moving on
+    pub fn cycle_mark(&mut self, coordinates: Coordinates, question_marks: bool) -> ChangeSet {
+        let mut changes = ChangeSet::from_status(self.status);
+        if self.status != GameStatus::InProgress || !self.is_covered(&coordinates) {
+            return changes;
+        }
+        let flag_count = self.flag_count_at(&coordinates);
+        if flag_count > 0 && flag_count < self.tile_map.max_bombs_per_tile() {
+            self.set_flag_count(coordinates, flag_count + 1, &mut changes);
+        } else if self.flagged.remove(&coordinates) {
+            self.set_flag_count(coordinates, 1, &mut changes);
+            changes.unflagged.push(coordinates);
+            if question_marks {
+                self.questioned.insert(coordinates);
+                changes.questioned.push(coordinates);
+            }
+        } else if self.questioned.remove(&coordinates) {
+            changes.unquestioned.push(coordinates);
+        } else {
+            self.flagged.insert(coordinates);
+            changes.flagged.push(coordinates);
+        }
+        changes
+    }
+
+    /// Reveals every unflagged covered neighbor of an uncovered number
+    /// once its neighbor flags mark as many bombs as its bomb count.
+    /// Wrong flags leave a bomb among the revealed tiles, ending the game
+    pub fn chord(&mut self, coordinates: Coordinates) -> ChangeSet {
+        let mut changes = ChangeSet::from_status(self.status);
+        if self.status != GameStatus::InProgress || self.is_covered(&coordinates) {
+            return changes;
+        }
+        let Some(Tile::BombNeighbor(count)) = self.tile_map.tile(coordinates) else {
+            return changes;
+        };
+        let neighbors: Vec<Coordinates> = self
+            .tile_map
+            .safe_square_at(coordinates)
+            .filter(|c| self.tile_map.tile(*c).is_some())
+            .collect();
+        let flagged_bombs: usize = neighbors.iter().map(|c| self.flag_count_at(c) as usize).sum();
+        if flagged_bombs != count as usize {
+            return changes;
+        }
+        for neighbor in neighbors {
+            changes.extend(self.reveal(neighbor));
+        }
+        changes
+    }
+
+    /// Applies previously computed changes, either replaying an action or reverting it
+    /// with the `ChangeSet::inverse` changes
+    pub fn apply(&mut self, changes: &ChangeSet) {
+        self.covered.extend(changes.covered.iter().copied());
+        for coordinates in &changes.uncovered {
+            self.covered.remove(coordinates);
+        }
+        for coordinates in &changes.unflagged {
+            self.flagged.remove(coordinates);
+        }
+        self.flagged.extend(changes.flagged.iter().copied());
+        for coordinates in &changes.unquestioned {
+            self.questioned.remove(coordinates);
+        }
+        self.questioned.extend(changes.questioned.iter().copied());
+        for change in &changes.flag_counts {
+            if change.count > 1 {
+                self.flag_counts.insert(change.coordinates, change.count);
+            } else {
+                self.flag_counts.remove(&change.coordinates);
+            }
+        }
+        self.status = changes.status;
+    }
+
+    /// Is every safe tile uncovered?
+    pub fn is_completed(&self) -> bool {
+        self.tile_map.bomb_tile_count() == self.covered.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::History;
+
+    /// 5x5 map with a column of bombs in the middle: `. 1 * 1 .` on every row
+    fn wall_game() -> Game {
+        let bombs: Vec<Coordinates> = (0..5).map(|y| Coordinates::at(2, y)).collect();
+        Game::new(TileMap::empty(5, 5).with_bombs(&bombs))
+    }
+
+    /// 3x3 map with a bomb in the bottom left corner, the center uncovered
+    fn chord_game() -> Game {
+        let mut game = Game::new(TileMap::empty(3, 3).with_bombs(&[Coordinates::at(0, 0)]));
+        game.reveal(Coordinates::at(1, 1));
+        game
+    }
+
+    type State = (Vec<Coordinates>, Vec<(Coordinates, u8)>, Vec<Coordinates>, GameStatus);
+
+    fn state(game: &Game) -> State {
+        (
+            game.covered().copied().collect(),
+            game.flagged().map(|c| (*c, game.flag_count_at(c))).collect(),
+            game.questioned().copied().collect(),
+            game.status(),
+        )
+    }
+
+    #[test]
+    fn reveal_floods_through_empty_tiles() {
+        let mut game = wall_game();
+        let changes = game.reveal(Coordinates::at(0, 0));
+        assert_eq!(changes.uncovered.len(), 10);
+        for y in 0..5 {
+            assert!(!game.is_covered(&Coordinates::at(0, y)));
+            assert!(!game.is_covered(&Coordinates::at(1, y)));
+            assert!(game.is_covered(&Coordinates::at(3, y)));
+        }
+        assert_eq!(game.status(), GameStatus::InProgress);
+    }
+
+    #[test]
+    fn reveal_stops_on_numbers() {
+        let mut game = wall_game();
+        let changes = game.reveal(Coordinates::at(3, 2));
+        assert_eq!(changes.uncovered, vec![Coordinates::at(3, 2)]);
+    }
+
+    #[test]
+    fn revealing_a_bomb_loses() {
+        let mut game = wall_game();
+        let changes = game.reveal(Coordinates::at(2, 2));
+        assert_eq!(game.status(), GameStatus::Lost);
+        assert_eq!(changes.exploded, Some(Coordinates::at(2, 2)));
+        assert_eq!(changes.previous_status, GameStatus::InProgress);
+        assert_eq!(changes.status, GameStatus::Lost);
+        // The game is over, nothing else can be revealed
+        assert!(game.reveal(Coordinates::at(0, 0)).is_empty());
+    }
+
+    #[test]
+    fn flagged_tiles_cant_be_revealed() {
+        let mut game = wall_game();
+        game.toggle_flag(Coordinates::at(0, 0));
+        assert!(game.reveal(Coordinates::at(0, 0)).is_empty());
+        assert!(game.is_covered(&Coordinates::at(0, 0)));
+    }
+
+    #[test]
+    fn uncovering_every_safe_tile_wins_and_flags_the_bombs() {
+        let mut game = Game::new(TileMap::empty(3, 1).with_bombs(&[Coordinates::at(0, 0)]));
+        let changes = game.reveal(Coordinates::at(2, 0));
+        assert_eq!(game.status(), GameStatus::Won);
+        assert_eq!(changes.flagged, vec![Coordinates::at(0, 0)]);
+        assert!(game.is_flagged(&Coordinates::at(0, 0)));
+        assert_eq!(game.flag_count(), 1);
+    }
+
+    #[test]
+    fn toggle_flag_flags_and_unflags() {
+        let mut game = wall_game();
+        let tile = Coordinates::at(4, 4);
+        let changes = game.toggle_flag(tile);
+        assert_eq!(changes.flagged, vec![tile]);
+        assert!(game.is_flagged(&tile));
+        assert_eq!(game.flag_count(), 1);
+        let changes = game.toggle_flag(tile);
+        assert_eq!(changes.unflagged, vec![tile]);
+        assert!(!game.is_flagged(&tile));
+        assert!(!game.is_questioned(&tile));
+        assert_eq!(game.flag_count(), 0);
+    }
+
+    #[test]
+    fn uncovered_tiles_cant_be_flagged() {
+        let mut game = wall_game();
+        game.reveal(Coordinates::at(0, 0));
+        assert!(game.toggle_flag(Coordinates::at(0, 0)).is_empty());
+    }
+
+    #[test]
+    fn cycle_mark_goes_through_the_question_mark() {
+        let mut game = wall_game();
+        let tile = Coordinates::at(4, 4);
+        game.cycle_mark(tile, true);
+        assert!(game.is_flagged(&tile));
+        let changes = game.cycle_mark(tile, true);
+        assert_eq!(changes.unflagged, vec![tile]);
+        assert_eq!(changes.questioned, vec![tile]);
+        assert!(game.is_questioned(&tile));
+        // Question marks don't block reveals, and are removed by them
+        let changes = game.reveal(tile);
+        assert_eq!(changes.unquestioned, vec![tile]);
+        assert!(!game.is_questioned(&tile));
+    }
+
+    #[test]
+    fn chord_reveals_the_neighbors_of_a_satisfied_number() {
+        let mut game = chord_game();
+        game.toggle_flag(Coordinates::at(0, 0));
+        let changes = game.chord(Coordinates::at(1, 1));
+        assert_eq!(changes.uncovered.len(), 7);
+        assert_eq!(game.status(), GameStatus::Won);
+    }
+
+    #[test]
+    fn chord_needs_as_many_flags_as_the_number() {
+        let mut game = chord_game();
+        assert!(game.chord(Coordinates::at(1, 1)).is_empty());
+        game.toggle_flag(Coordinates::at(0, 0));
+        game.toggle_flag(Coordinates::at(2, 2));
+        assert!(game.chord(Coordinates::at(1, 1)).is_empty());
+        assert_eq!(game.status(), GameStatus::InProgress);
+    }
+
+    #[test]
+    fn chord_with_a_wrong_flag_loses() {
+        let mut game = chord_game();
+        game.toggle_flag(Coordinates::at(2, 2));
+        let changes = game.chord(Coordinates::at(1, 1));
+        assert_eq!(changes.exploded, Some(Coordinates::at(0, 0)));
+        assert_eq!(game.status(), GameStatus::Lost);
+    }
+
+    #[test]
+    fn chord_ignores_covered_tiles() {
+        let mut game = chord_game();
+        assert!(game.chord(Coordinates::at(2, 2)).is_empty());
+    }
+
+    #[test]
+    fn undo_and_redo_round_trip() {
+        let mut game = wall_game();
+        let mut history = History::default();
+        let initial = state(&game);
+        let mut states = Vec::new();
+        let moves: [fn(&mut Game) -> ChangeSet; 4] = [
+            |game| game.reveal(Coordinates::at(0, 0)),
+            |game| game.cycle_mark(Coordinates::at(4, 4), true),
+            |game| game.cycle_mark(Coordinates::at(4, 4), true),
+            |game| game.reveal(Coordinates::at(4, 0)),
+        ];
+        for play in moves {
+            history.record(&play(&mut game));
+            states.push(state(&game));
+        }
+        assert_eq!(game.status(), GameStatus::Won);
+        for expected in states.iter().rev().skip(1).chain([&initial]) {
+            history.undo(&mut game).unwrap();
+            assert_eq!(&state(&game), expected);
+        }
+        assert!(!history.can_undo());
+        for expected in &states {
+            history.redo(&mut game).unwrap();
+            assert_eq!(&state(&game), expected);
+        }
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn undoing_a_loss_resumes_the_game() {
+        let mut game = wall_game();
+        let mut history = History::default();
+        history.record(&game.reveal(Coordinates::at(0, 0)));
+        let before = state(&game);
+        history.record(&game.reveal(Coordinates::at(2, 0)));
+        assert_eq!(game.status(), GameStatus::Lost);
+        let changes = history.undo(&mut game).unwrap();
+        assert_eq!(changes.status, GameStatus::InProgress);
+        assert_eq!(state(&game), before);
+    }
+
+    #[test]
+    fn flags_count_up_to_the_max_bombs_per_tile() {
+        let mut tile_map = TileMap::empty(3, 1);
+        tile_map.set_max_bombs_per_tile(3);
+        let mut game = Game::new(tile_map.with_bombs(&[Coordinates::at(0, 0), Coordinates::at(0, 0)]));
+        let mut history = History::default();
+        let tile = Coordinates::at(0, 0);
+        for count in 1..=3 {
+            history.record(&game.cycle_mark(tile, true));
+            assert_eq!(game.flag_count_at(&tile), count);
+            assert_eq!(game.flag_count(), count as usize);
+        }
+        history.record(&game.cycle_mark(tile, true));
+        assert!(game.is_questioned(&tile));
+        assert_eq!(game.flag_count(), 0);
+        history.undo(&mut game);
+        assert_eq!(game.flag_count_at(&tile), 3);
+    }
+
+    #[test]
+    fn chord_counts_the_flag_quantities() {
+        let mut tile_map = TileMap::empty(3, 1);
+        tile_map.set_max_bombs_per_tile(2);
+        let mut game = Game::new(tile_map.with_bombs(&[Coordinates::at(0, 0), Coordinates::at(0, 0)]));
+        game.reveal(Coordinates::at(1, 0));
+        game.toggle_flag(Coordinates::at(0, 0));
+        assert!(game.chord(Coordinates::at(1, 0)).is_empty());
+        game.toggle_flag(Coordinates::at(0, 0));
+        assert_eq!(game.flag_count_at(&Coordinates::at(0, 0)), 2);
+        game.chord(Coordinates::at(1, 0));
+        assert_eq!(game.status(), GameStatus::Won);
+    }
+}
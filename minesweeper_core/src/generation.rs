@@ -0,0 +1,149 @@
+use std::time::{Duration, Instant};
+use log::{info, warn};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use crate::Coordinates;
+use crate::solver::Solver;
+use crate::tile_map::TileMap;
+
+/// Time `NoGuessFallback::KeepSearching` may spend past the budget before keeping the last board.
+/// Some boards have no no-guess layout, or are too dense to find one
+const KEEP_SEARCHING_MAX_DURATION: Duration = Duration::from_secs(5);
+
+/// What to do when no-guess generation runs out of budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NoGuessFallback {
+    /// Keep the last generated board, even if it requires guessing
+    UseLastAttempt,
+    /// Ignore the budget and keep generating until a solvable board is found,
+    /// giving up after `KEEP_SEARCHING_MAX_DURATION` more
+    KeepSearching,
+}
+
+/// Bomb placement strategy
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub enum BoardGeneration {
+    /// Bombs are placed at random
+    #[default]
+    Random,
+    /// Boards are generated until one can be cleared by deduction alone from the first
+    /// tile of the safe area, or from the safe start tile. Meant to be used with `safe_start`
+    /// or a deferred `first_click`
+    NoGuess {
+        /// Maximum number of generated boards, at least one
+        max_attempts: u32,
+        /// Maximum time spent generating boards
+        max_duration: Duration,
+        /// Policy once the budget is exhausted
+        fallback: NoGuessFallback,
+    },
+}
+
+impl BoardGeneration {
+    /// Generates a tile map shaped like `empty_map` holding `bomb_count` bombs,
+    /// none of them in `safe_area`
+    pub fn generate<R: RngCore + ?Sized>(
+        &self,
+        empty_map: &TileMap,
+        bomb_count: u16,
+        rng: &mut R,
+        safe_area: &[Coordinates],
+    ) -> TileMap {
+        let (max_attempts, max_duration, fallback) = match self {
+            BoardGeneration::Random => {
+                let mut tile_map = empty_map.clone();
+                tile_map.set_bombs_avoiding(bomb_count, rng, safe_area);
+                return tile_map;
+            }
+            BoardGeneration::NoGuess { max_attempts, max_duration, fallback } => {
+                (*max_attempts, *max_duration, *fallback)
+            }
+        };
+
+        let started = Instant::now();
+        let mut attempts: u32 = 0;
+        // When the budget ran out, KeepSearching goes on for a bounded time past it
+        let mut over_budget_since: Option<Instant> = None;
+        loop {
+            let mut tile_map = empty_map.clone();
+            tile_map.set_bombs_avoiding(bomb_count, rng, safe_area);
+            attempts += 1;
+
+            let solvable = safe_area
+                .first()
+                .copied()
+                .or_else(|| tile_map.safe_start())
+                .is_some_and(|start| Solver::is_solvable_from(&tile_map, start));
+            if solvable {
+                info!("Generated a no-guess board after {} attempts", attempts);
+                return tile_map;
+            }
+
+            let elapsed = started.elapsed();
+            if attempts < max_attempts && elapsed < max_duration {
+                continue;
+            }
+            if fallback == NoGuessFallback::KeepSearching && over_budget_since.is_none() {
+                warn!("No-guess generation exceeded its budget, still searching");
+                over_budget_since = Some(Instant::now());
+            }
+            let keep_searching = over_budget_since
+                .is_some_and(|since| since.elapsed() < KEEP_SEARCHING_MAX_DURATION);
+            if !keep_searching {
+                warn!("No-guess generation gave up after {} attempts", attempts);
+                return tile_map;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use super::*;
+
+    #[test]
+    fn no_guess_boards_are_solvable() {
+        let generation = BoardGeneration::NoGuess {
+            max_attempts: 1000,
+            max_duration: Duration::from_secs(10),
+            fallback: NoGuessFallback::UseLastAttempt,
+        };
+        for seed in 0..5 {
+            let tile_map = generation.generate(&TileMap::empty(9, 9), 10, &mut StdRng::seed_from_u64(seed), &[]);
+            let start = tile_map.safe_start().unwrap();
+            assert!(Solver::is_solvable_from(&tile_map, start));
+        }
+    }
+
+    #[test]
+    fn no_guess_boards_are_solvable_from_the_safe_area() {
+        let generation = BoardGeneration::NoGuess {
+            max_attempts: 1000,
+            max_duration: Duration::from_secs(10),
+            fallback: NoGuessFallback::UseLastAttempt,
+        };
+        let start = Coordinates { coord_x: 4, coord_y: 4 };
+        let safe_area: Vec<Coordinates> = std::iter::once(start)
+            .chain((-1..=1).flat_map(|y| (-1..=1).map(move |x| start + (x, y))))
+            .collect();
+        for seed in 0..5 {
+            let tile_map = generation.generate(&TileMap::empty(9, 9), 10, &mut StdRng::seed_from_u64(seed), &safe_area);
+            assert!(safe_area.iter().all(|c| !tile_map.is_bomb_at(*c)));
+            assert!(Solver::is_solvable_from(&tile_map, start));
+        }
+    }
+
+    #[test]
+    fn no_guess_generation_keeps_the_last_board_out_of_attempts() {
+        // The only safe tile is a number, there is no empty tile to start from
+        let generation = BoardGeneration::NoGuess {
+            max_attempts: 3,
+            max_duration: Duration::from_secs(10),
+            fallback: NoGuessFallback::UseLastAttempt,
+        };
+        let tile_map = generation.generate(&TileMap::empty(2, 2), 3, &mut StdRng::seed_from_u64(0), &[]);
+        assert_eq!(tile_map.bomb_count(), 3);
+    }
+}
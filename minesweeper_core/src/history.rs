@@ -0,0 +1,44 @@
+use crate::game::{ChangeSet, Game};
+
+/// Undo and redo stacks of the actions played on a game
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    done: Vec<ChangeSet>,
+    undone: Vec<ChangeSet>,
+}
+
+impl History {
+    /// Records the changes of a new action, which drops the undone ones
+    pub fn record(&mut self, changes: &ChangeSet) {
+        if changes.is_empty() {
+            return;
+        }
+        self.done.push(changes.clone());
+        self.undone.clear();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.done.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// Reverts the last action, returning the changes applied to `game`
+    pub fn undo(&mut self, game: &mut Game) -> Option<ChangeSet> {
+        let changes = self.done.pop()?;
+        let inverse = changes.inverse();
+        game.apply(&inverse);
+        self.undone.push(changes);
+        Some(inverse)
+    }
+
+    /// Replays the last undone action, returning the changes applied to `game`
+    pub fn redo(&mut self, game: &mut Game) -> Option<ChangeSet> {
+        let changes = self.undone.pop()?;
+        game.apply(&changes);
+        self.done.push(changes.clone());
+        Some(changes)
+    }
+}
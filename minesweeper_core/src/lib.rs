@@ -0,0 +1,17 @@
+//! Engine independent minesweeper rules
+pub use coordinates::Coordinates;
+pub use game::*;
+pub use generation::*;
+pub use history::History;
+pub use mask::{BoardMask, BoardMaskError};
+pub use topology::{BoardTopology, Neighborhood};
+pub mod tile;
+pub mod tile_map;
+pub mod solver;
+pub mod probability;
+mod coordinates;
+mod game;
+mod generation;
+mod history;
+mod mask;
+mod topology;
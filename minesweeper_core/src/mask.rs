@@ -0,0 +1,213 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+use crate::Coordinates;
+
+/// Character of a tile in a mask description
+const TILE_CHAR: char = '#';
+/// Characters of a hole in a mask description
+const HOLE_CHARS: [char; 2] = ['.', ' '];
+
+/// Shape of a board: the cells of its bounding rectangle which are holes instead of tiles.
+///
+/// Described as text, one line per row from the top one, `#` being a tile and `.` or a space
+/// a hole. Short lines are padded with holes:
+/// ```text
+/// .##.##.
+/// #######
+/// .#####.
+/// ..###..
+/// ...#...
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct BoardMask {
+    width: u16,
+    height: u16,
+    holes: BTreeSet<Coordinates>,
+}
+
+impl BoardMask {
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    pub fn holes(&self) -> &BTreeSet<Coordinates> {
+        &self.holes
+    }
+
+    /// Number of tiles, holes excluded
+    pub fn tile_count(&self) -> u32 {
+        self.width as u32 * self.height as u32 - self.holes.len() as u32
+    }
+}
+
+/// Reason why a mask description can't be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BoardMaskError {
+    /// The description holds no tile
+    Empty,
+    /// The description doesn't fit in a board
+    TooLarge,
+    /// A character is neither a tile nor a hole, `line` and `column` start at 1
+    InvalidChar { line: usize, column: usize, char: char },
+}
+
+impl Display for BoardMaskError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            BoardMaskError::Empty => write!(f, "the mask holds no tile"),
+            BoardMaskError::TooLarge => write!(f, "the mask is too large"),
+            BoardMaskError::InvalidChar { line, column, char } => write!(
+                f,
+                "invalid mask character {:?} at {}:{}, expected {:?} or {:?}",
+                char, line, column, TILE_CHAR, HOLE_CHARS[0]
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BoardMaskError {}
+
+impl FromStr for BoardMask {
+    type Err = BoardMaskError;
+
+    fn from_str(description: &str) -> Result<Self, Self::Err> {
+        let lines: Vec<&str> = description
+            .lines()
+            .map(|line| line.trim_end_matches(HOLE_CHARS))
+            .skip_while(|line| line.is_empty())
+            .collect();
+        let lines = match lines.iter().rposition(|line| !line.is_empty()) {
+            Some(last) => &lines[..=last],
+            None => return Err(BoardMaskError::Empty),
+        };
+        let width = lines.iter().map(|line| line.chars().count()).max().unwrap_or_default();
+        let width = u16::try_from(width).map_err(|_| BoardMaskError::TooLarge)?;
+        let height = u16::try_from(lines.len()).map_err(|_| BoardMaskError::TooLarge)?;
+
+        let mut holes = BTreeSet::new();
+        for (index, line) in lines.iter().enumerate() {
+            // The first line is the top row
+            let coord_y = height - 1 - index as u16;
+            let mut chars = line.chars();
+            for coord_x in 0..width {
+                match chars.next() {
+                    Some(TILE_CHAR) => (),
+                    Some(char) if !HOLE_CHARS.contains(&char) => {
+                        return Err(BoardMaskError::InvalidChar {
+                            line: index + 1,
+                            column: coord_x as usize + 1,
+                            char,
+                        });
+                    }
+                    _ => {
+                        holes.insert(Coordinates { coord_x, coord_y });
+                    }
+                }
+            }
+        }
+        Ok(Self { width, height, holes })
+    }
+}
+
+impl TryFrom<String> for BoardMask {
+    type Error = BoardMaskError;
+
+    fn try_from(description: String) -> Result<Self, Self::Error> {
+        description.parse()
+    }
+}
+
+impl Display for BoardMask {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for coord_y in (0..self.height).rev() {
+            let line: String = (0..self.width)
+                .map(|coord_x| match self.holes.contains(&Coordinates { coord_x, coord_y }) {
+                    true => HOLE_CHARS[0],
+                    false => TILE_CHAR,
+                })
+                .collect();
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<BoardMask> for String {
+    fn from(mask: BoardMask) -> Self {
+        mask.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_tiles_and_holes_from_the_top_row() {
+        let mask: BoardMask = "#.#\n###".parse().unwrap();
+        assert_eq!(mask.width(), 3);
+        assert_eq!(mask.height(), 2);
+        assert_eq!(mask.holes(), &BTreeSet::from([Coordinates::at(1, 1)]));
+        assert_eq!(mask.tile_count(), 5);
+    }
+
+    #[test]
+    fn short_lines_are_padded_with_holes() {
+        let mask: BoardMask = "###\n#\n # ".parse().unwrap();
+        assert_eq!(mask.width(), 3);
+        assert_eq!(mask.height(), 3);
+        assert_eq!(
+            mask.holes(),
+            &BTreeSet::from([
+                Coordinates::at(0, 0),
+                Coordinates::at(2, 0),
+                Coordinates::at(1, 1),
+                Coordinates::at(2, 1),
+            ])
+        );
+    }
+
+    #[test]
+    fn blank_lines_and_trailing_holes_are_trimmed() {
+        let mask: BoardMask = "\n...\n.#..\n##.\n\n".parse().unwrap();
+        assert_eq!(mask.width(), 2);
+        assert_eq!(mask.height(), 2);
+        assert_eq!(mask.holes(), &BTreeSet::from([Coordinates::at(0, 1)]));
+    }
+
+    #[test]
+    fn display_round_trips() {
+        let description = ".##.##.\n#######\n.#####.\n..###..\n...#...\n";
+        let mask: BoardMask = description.parse().unwrap();
+        assert_eq!(mask.to_string(), description);
+        assert_eq!(mask.to_string().parse::<BoardMask>().unwrap(), mask);
+    }
+
+    #[test]
+    fn rejects_empty_masks() {
+        assert_eq!("".parse::<BoardMask>(), Err(BoardMaskError::Empty));
+        assert_eq!(" ..\n\n.".parse::<BoardMask>(), Err(BoardMaskError::Empty));
+    }
+
+    #[test]
+    fn rejects_invalid_characters_with_their_position() {
+        assert_eq!(
+            "###\n#x#".parse::<BoardMask>(),
+            Err(BoardMaskError::InvalidChar { line: 2, column: 2, char: 'x' })
+        );
+    }
+
+    #[test]
+    fn rejects_masks_larger_than_a_board() {
+        let description = "#".repeat(u16::MAX as usize + 1);
+        assert_eq!(description.parse::<BoardMask>(), Err(BoardMaskError::TooLarge));
+    }
+}
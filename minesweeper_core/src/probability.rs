@@ -0,0 +1,358 @@
+use std::collections::{BTreeSet, HashMap};
+use crate::{Coordinates, Game};
+use crate::tile::Tile;
+
+/// Covered tiles around an uncovered number and its bomb count
+type NumberConstraint = (Vec<Coordinates>, usize);
+
+/// Most configurations explored before giving up on a board
+const MAX_EXPLORED_NODES: usize = 2_000_000;
+
+/// Exact probability of every covered tile of `game` to hold a bomb, given the uncovered numbers
+/// and the total bomb count. Flags are not trusted, they may be wrong.
+///
+/// Covered tiles next to a number (the frontier) are split into independent groups whose bomb
+/// configurations are enumerated, then weighted by the ways to place the remaining bombs on the
+/// other covered tiles. Returns `None` when the frontier is too large to enumerate, or when
+/// tiles can hold several bombs
+pub fn mine_probabilities(game: &Game) -> Option<HashMap<Coordinates, f64>> {
+    let tile_map = game.tile_map();
+    if tile_map.max_bombs_per_tile() > 1 {
+        return None;
+    }
+    let covered: Vec<Coordinates> = game.covered().copied().collect();
+    let bomb_count = tile_map.bomb_count() as usize;
+
+    // One constraint per uncovered number touching covered tiles
+    let mut constraints: Vec<NumberConstraint> = Vec::new();
+    for coord_y in 0..tile_map.height() {
+        for coord_x in 0..tile_map.width() {
+            let coordinates = Coordinates { coord_x, coord_y };
+            if game.is_covered(&coordinates) {
+                continue;
+            }
+            let Some(Tile::BombNeighbor(count)) = tile_map.tile(coordinates) else { continue };
+            let tiles: Vec<Coordinates> = tile_map
+                .safe_square_at(coordinates)
+                .filter(|neighbor| tile_map.tile(*neighbor).is_some() && game.is_covered(neighbor))
+                .collect();
+            if !tiles.is_empty() {
+                constraints.push((tiles, count as usize));
+            }
+        }
+    }
+
+    let mut budget = MAX_EXPLORED_NODES;
+    let groups = frontier_groups(&constraints)
+        .into_iter()
+        .map(|(tiles, group_constraints)| Group::enumerate(tiles, &group_constraints, &mut budget))
+        .collect::<Option<Vec<Group>>>()?;
+
+    let frontier_size: usize = groups.iter().map(|group| group.tiles.len()).sum();
+    let other_count = covered.len() - frontier_size;
+    let weights = remaining_bomb_weights(other_count, bomb_count, frontier_size);
+
+    let all_counts = groups
+        .iter()
+        .fold(vec![1.0], |counts, group| convolve(&counts, &group.counts));
+    let total: f64 = all_counts
+        .iter()
+        .enumerate()
+        .map(|(bombs, count)| count * weights[bombs])
+        .sum();
+    if total <= 0.0 {
+        // The numbers contradict the bomb count
+        return None;
+    }
+
+    let mut probabilities = HashMap::with_capacity(covered.len());
+    for (index, group) in groups.iter().enumerate() {
+        let other_counts = groups
+            .iter()
+            .enumerate()
+            .filter(|(other_index, _)| *other_index != index)
+            .fold(vec![1.0], |counts, (_, other)| convolve(&counts, &other.counts));
+        for (tile_index, tile) in group.tiles.iter().enumerate() {
+            let mut weight = 0.0;
+            for (group_bombs, bomb_counts) in group.bomb_counts.iter().enumerate() {
+                let rest: f64 = other_counts
+                    .iter()
+                    .enumerate()
+                    .map(|(other_bombs, count)| count * weights[group_bombs + other_bombs])
+                    .sum();
+                weight += bomb_counts[tile_index] * rest;
+            }
+            probabilities.insert(*tile, weight / total);
+        }
+    }
+
+    if other_count > 0 {
+        // Remaining bombs are spread evenly on the tiles no number sees
+        let other_bombs: f64 = all_counts
+            .iter()
+            .enumerate()
+            .map(|(bombs, count)| {
+                count * weights[bombs] * bomb_count.saturating_sub(bombs) as f64
+            })
+            .sum();
+        let probability = other_bombs / total / other_count as f64;
+        for tile in &covered {
+            probabilities.entry(*tile).or_insert(probability);
+        }
+    }
+    Some(probabilities)
+}
+
+/// Bomb configurations of a group of frontier tiles, indexed by their bomb count
+struct Group {
+    tiles: Vec<Coordinates>,
+    /// Number of configurations with `index` bombs
+    counts: Vec<f64>,
+    /// Per tile, number of configurations with `index` bombs where the tile holds one
+    bomb_counts: Vec<Vec<f64>>,
+}
+
+impl Group {
+    /// Enumerates the configurations satisfying every constraint, `None` once out of budget
+    fn enumerate(
+        tiles: Vec<Coordinates>,
+        constraints: &[NumberConstraint],
+        budget: &mut usize,
+    ) -> Option<Self> {
+        let index: HashMap<Coordinates, usize> =
+            tiles.iter().enumerate().map(|(i, tile)| (*tile, i)).collect();
+        let constraints: Vec<(Vec<usize>, usize)> = constraints
+            .iter()
+            .map(|(constraint_tiles, bombs)| {
+                (constraint_tiles.iter().map(|tile| index[tile]).collect(), *bombs)
+            })
+            .collect();
+        let mut tile_constraints = vec![Vec::new(); tiles.len()];
+        for (constraint_index, (constraint_tiles, _)) in constraints.iter().enumerate() {
+            for tile in constraint_tiles {
+                tile_constraints[*tile].push(constraint_index);
+            }
+        }
+
+        let mut enumeration = Enumeration {
+            tile_constraints,
+            needed: constraints.iter().map(|(_, bombs)| *bombs as isize).collect(),
+            unassigned: constraints.iter().map(|(tiles, _)| tiles.len()).collect(),
+            assignment: vec![false; tiles.len()],
+            counts: vec![0.0; tiles.len() + 1],
+            bomb_counts: vec![vec![0.0; tiles.len()]; tiles.len() + 1],
+            budget,
+        };
+        if !enumeration.assign(0, 0) {
+            return None;
+        }
+        Some(Self {
+            tiles,
+            counts: enumeration.counts,
+            bomb_counts: enumeration.bomb_counts,
+        })
+    }
+}
+
+/// Backtracking state of a group enumeration
+struct Enumeration<'a> {
+    tile_constraints: Vec<Vec<usize>>,
+    /// Bombs each constraint still needs
+    needed: Vec<isize>,
+    /// Tiles of each constraint left to assign
+    unassigned: Vec<usize>,
+    assignment: Vec<bool>,
+    counts: Vec<f64>,
+    bomb_counts: Vec<Vec<f64>>,
+    budget: &'a mut usize,
+}
+
+impl Enumeration<'_> {
+    /// Assigns the tiles from `tile` on, returns false once out of budget
+    fn assign(&mut self, tile: usize, bombs: usize) -> bool {
+        if *self.budget == 0 {
+            return false;
+        }
+        *self.budget -= 1;
+        if tile == self.assignment.len() {
+            self.counts[bombs] += 1.0;
+            for (index, is_bomb) in self.assignment.iter().enumerate() {
+                if *is_bomb {
+                    self.bomb_counts[bombs][index] += 1.0;
+                }
+            }
+            return true;
+        }
+        for is_bomb in [false, true] {
+            let delta = is_bomb as isize;
+            let mut valid = true;
+            for constraint in &self.tile_constraints[tile] {
+                self.needed[*constraint] -= delta;
+                self.unassigned[*constraint] -= 1;
+                let needed = self.needed[*constraint];
+                valid &= needed >= 0 && needed as usize <= self.unassigned[*constraint];
+            }
+            self.assignment[tile] = is_bomb;
+            let within_budget = !valid || self.assign(tile + 1, bombs + is_bomb as usize);
+            for constraint in &self.tile_constraints[tile] {
+                self.needed[*constraint] += delta;
+                self.unassigned[*constraint] += 1;
+            }
+            if !within_budget {
+                return false;
+            }
+        }
+        self.assignment[tile] = false;
+        true
+    }
+}
+
+/// Splits the constrained tiles into groups sharing no constraint, tiles ordered so that
+/// neighbors are assigned one after the other
+fn frontier_groups(
+    constraints: &[NumberConstraint],
+) -> Vec<(Vec<Coordinates>, Vec<NumberConstraint>)> {
+    let mut tile_constraints: HashMap<Coordinates, Vec<usize>> = HashMap::new();
+    for (index, (tiles, _)) in constraints.iter().enumerate() {
+        for tile in tiles {
+            tile_constraints.entry(*tile).or_default().push(index);
+        }
+    }
+
+    let mut visited = BTreeSet::new();
+    let mut groups = Vec::new();
+    let starts: BTreeSet<Coordinates> = tile_constraints.keys().copied().collect();
+    for start in starts {
+        if !visited.insert(start) {
+            continue;
+        }
+        let mut tiles = Vec::new();
+        let mut group_constraints = BTreeSet::new();
+        let mut queue = std::collections::VecDeque::from([start]);
+        while let Some(tile) = queue.pop_front() {
+            tiles.push(tile);
+            for constraint in &tile_constraints[&tile] {
+                group_constraints.insert(*constraint);
+                for neighbor in &constraints[*constraint].0 {
+                    if visited.insert(*neighbor) {
+                        queue.push_back(*neighbor);
+                    }
+                }
+            }
+        }
+        let group_constraints = group_constraints
+            .into_iter()
+            .map(|index| constraints[index].clone())
+            .collect();
+        groups.push((tiles, group_constraints));
+    }
+    groups
+}
+
+/// Relative number of ways to place the bombs left once the frontier holds `index` bombs,
+/// on the `other_count` tiles no number sees
+fn remaining_bomb_weights(other_count: usize, bomb_count: usize, frontier_size: usize) -> Vec<f64> {
+    // ln(n!) table, binomials overflow f64 on large boards
+    let ln_factorials: Vec<f64> = std::iter::once(0.0)
+        .chain((1..=other_count).scan(0.0, |sum, n| {
+            *sum += (n as f64).ln();
+            Some(*sum)
+        }))
+        .collect();
+    let ln_binomial = |n: usize, k: usize| ln_factorials[n] - ln_factorials[k] - ln_factorials[n - k];
+
+    let ln_weights: Vec<Option<f64>> = (0..=frontier_size)
+        .map(|frontier_bombs| {
+            let remaining = bomb_count.checked_sub(frontier_bombs)?;
+            (remaining <= other_count).then(|| ln_binomial(other_count, remaining))
+        })
+        .collect();
+    let max = ln_weights
+        .iter()
+        .flatten()
+        .copied()
+        .fold(f64::NEG_INFINITY, f64::max);
+    ln_weights
+        .into_iter()
+        .map(|ln_weight| ln_weight.map_or(0.0, |ln_weight| (ln_weight - max).exp()))
+        .collect()
+}
+
+/// Configuration counts of two independent groups combined, indexed by bomb count
+fn convolve(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let mut result = vec![0.0; a.len() + b.len() - 1];
+    for (i, a_count) in a.iter().enumerate() {
+        for (j, b_count) in b.iter().enumerate() {
+            result[i + j] += a_count * b_count;
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tile_map::TileMap;
+
+    fn assert_close(probability: f64, expected: f64) {
+        assert!((probability - expected).abs() < 1e-9, "{} != {}", probability, expected);
+    }
+
+    #[test]
+    fn covered_tiles_share_the_bombs_before_any_reveal() {
+        let bombs: Vec<Coordinates> = (0..5).map(|y| Coordinates::at(2, y)).collect();
+        let game = Game::new(TileMap::empty(5, 5).with_bombs(&bombs));
+        let probabilities = mine_probabilities(&game).unwrap();
+        assert_eq!(probabilities.len(), 25);
+        for probability in probabilities.values() {
+            assert_close(*probability, 0.2);
+        }
+    }
+
+    #[test]
+    fn deduced_tiles_are_certain() {
+        let bombs: Vec<Coordinates> = (0..5).map(|y| Coordinates::at(2, y)).collect();
+        let mut game = Game::new(TileMap::empty(5, 5).with_bombs(&bombs));
+        game.reveal(Coordinates::at(0, 0));
+        let probabilities = mine_probabilities(&game).unwrap();
+        assert_eq!(probabilities.len(), 15);
+        for (coordinates, probability) in probabilities {
+            let expected = if coordinates.coord_x == 2 { 1.0 } else { 0.0 };
+            assert_close(probability, expected);
+        }
+    }
+
+    #[test]
+    fn fifty_fifty_tiles_are_even() {
+        let mut game = Game::new(TileMap::empty(3, 2).with_bombs(&[Coordinates::at(2, 1)]));
+        game.reveal(Coordinates::at(0, 0));
+        let probabilities = mine_probabilities(&game).unwrap();
+        assert_eq!(probabilities.len(), 2);
+        assert_close(probabilities[&Coordinates::at(2, 0)], 0.5);
+        assert_close(probabilities[&Coordinates::at(2, 1)], 0.5);
+    }
+
+    #[test]
+    fn probabilities_sum_to_the_bomb_count() {
+        let tile_map = TileMap::empty(6, 3).with_bombs(&[
+            Coordinates::at(3, 0),
+            Coordinates::at(5, 1),
+            Coordinates::at(4, 2),
+        ]);
+        let mut game = Game::new(tile_map);
+        game.reveal(Coordinates::at(0, 0));
+        let probabilities = mine_probabilities(&game).unwrap();
+        assert_close(probabilities.values().sum(), 3.0);
+        // Tiles away from the numbers weigh as much as each other
+        let other = probabilities[&Coordinates::at(5, 0)];
+        assert_close(probabilities[&Coordinates::at(5, 2)], other);
+    }
+
+    #[test]
+    fn tiles_holding_several_bombs_have_no_probabilities() {
+        let mut tile_map = TileMap::empty(3, 3);
+        tile_map.set_max_bombs_per_tile(2);
+        let game = Game::new(tile_map.with_bombs(&[Coordinates::at(0, 0)]));
+        assert_eq!(mine_probabilities(&game), None);
+    }
+}
@@ -0,0 +1,385 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use crate::{Coordinates, Game};
+use crate::tile::Tile;
+use crate::tile_map::TileMap;
+
+/// Tiles proven safe or proven to be bombs by the solver
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Deduction {
+    /// Covered tiles which can be safely uncovered
+    pub safe: BTreeSet<Coordinates>,
+    /// Covered tiles which must hold a bomb, as many as a tile can hold
+    pub bombs: BTreeSet<Coordinates>,
+}
+
+impl Deduction {
+    pub fn is_empty(&self) -> bool {
+        self.safe.is_empty() && self.bombs.is_empty()
+    }
+}
+
+/// Tile the player can act on, proven from the visible numbers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hint {
+    /// Covered tile which can be safely uncovered
+    Safe(Coordinates),
+    /// Covered tile which must hold a bomb, unflagged or flagged with fewer bombs than it holds
+    Bomb(Coordinates),
+}
+
+impl Hint {
+    pub fn coordinates(&self) -> Coordinates {
+        match self {
+            Hint::Safe(coordinates) | Hint::Bomb(coordinates) => *coordinates,
+        }
+    }
+}
+
+/// The `tiles` hold exactly `bombs` bombs
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Constraint {
+    tiles: BTreeSet<Coordinates>,
+    bombs: usize,
+}
+
+/// Logical solver only relying on what the player can see: uncovered numbers,
+/// already deduced bombs and the total bomb count
+#[derive(Debug, Clone)]
+pub struct Solver<'a> {
+    tile_map: &'a TileMap,
+    uncovered: HashSet<Coordinates>,
+    bombs: HashSet<Coordinates>,
+}
+
+impl<'a> Solver<'a> {
+    pub fn new(tile_map: &'a TileMap) -> Self {
+        Self {
+            tile_map,
+            uncovered: HashSet::new(),
+            bombs: HashSet::new(),
+        }
+    }
+
+    /// Solver seeing the uncovered tiles of `game`. Flags are not trusted, they may be wrong
+    pub fn from_game(game: &'a Game) -> Self {
+        let tile_map = game.tile_map();
+        let uncovered = tile_map
+            .coordinates()
+            .filter(|coordinates| !game.is_covered(coordinates))
+            .collect();
+        Self {
+            tile_map,
+            uncovered,
+            bombs: HashSet::new(),
+        }
+    }
+
+    /// Finds a tile of `game` to act on, preferring safe tiles over bombs left to flag.
+    /// Deduced bombs are kept between deductions, so they help proving safe tiles
+    pub fn hint(game: &Game) -> Option<Hint> {
+        let mut solver = Solver::from_game(game);
+        let mut bomb_hint = None;
+        loop {
+            let deduction = solver.deduce();
+            if let Some(safe) = deduction.safe.first() {
+                return Some(Hint::Safe(*safe));
+            }
+            if deduction.bombs.is_empty() {
+                return bomb_hint;
+            }
+            let max_bombs = game.tile_map().max_bombs_per_tile();
+            bomb_hint = bomb_hint.or_else(|| {
+                deduction
+                    .bombs
+                    .iter()
+                    .find(|coordinates| game.flag_count_at(coordinates) < max_bombs)
+                    .map(|coordinates| Hint::Bomb(*coordinates))
+            });
+            solver.bombs.extend(deduction.bombs);
+        }
+    }
+
+    /// Checks if the tile map can be fully cleared from `start` without guessing
+    pub fn is_solvable_from(tile_map: &TileMap, start: Coordinates) -> bool {
+        let mut solver = Solver::new(tile_map);
+        solver.uncover(start) && solver.solve()
+    }
+
+    /// Uncovers a tile, propagating through empty tiles.
+    /// Returns false if a bomb was uncovered
+    pub fn uncover(&mut self, coordinates: Coordinates) -> bool {
+        let mut stack = vec![coordinates];
+        while let Some(coordinates) = stack.pop() {
+            let Some(tile) = self.tile_map.tile(coordinates) else { continue };
+            if !self.uncovered.insert(coordinates) {
+                continue;
+            }
+            match tile {
+                Tile::Bomb(_) => return false,
+                Tile::Empty => stack.extend(self.tile_map.safe_square_at(coordinates)),
+                Tile::BombNeighbor(_) => (),
+            }
+        }
+        true
+    }
+
+    /// Applies deductions until the board is cleared or no progress can be made.
+    /// Returns true if the board was cleared
+    pub fn solve(&mut self) -> bool {
+        while !self.is_solved() {
+            let deduction = self.deduce();
+            if deduction.is_empty() {
+                return false;
+            }
+            self.bombs.extend(deduction.bombs);
+            for coordinates in deduction.safe {
+                if !self.uncover(coordinates) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    /// Is every safe tile uncovered?
+    pub fn is_solved(&self) -> bool {
+        self.uncovered.len() + self.tile_map.bomb_tile_count() == self.tile_map.tile_count()
+    }
+
+    /// Finds provably safe tiles and provable bombs, trying the cheapest reasoning first
+    pub fn deduce(&self) -> Deduction {
+        let constraints = self.constraints();
+        let max_bombs = self.max_bombs();
+        let deduction = Self::single_tile_deduction(&constraints, max_bombs);
+        if !deduction.is_empty() {
+            return deduction;
+        }
+        let deduction = Self::constraint_pair_deduction(&constraints, max_bombs);
+        if !deduction.is_empty() {
+            return deduction;
+        }
+        self.bomb_count_deduction()
+    }
+
+    /// Bombs held by a deduced bomb tile: deductions only prove tiles holding as many as they can
+    fn max_bombs(&self) -> usize {
+        self.tile_map.max_bombs_per_tile() as usize
+    }
+
+    fn is_unknown(&self, coordinates: &Coordinates) -> bool {
+        !self.uncovered.contains(coordinates) && !self.bombs.contains(coordinates)
+    }
+
+    /// One constraint per uncovered number still touching unknown tiles
+    fn constraints(&self) -> Vec<Constraint> {
+        let mut constraints = Vec::new();
+        for coordinates in &self.uncovered {
+            let Some(Tile::BombNeighbor(count)) = self.tile_map.tile(*coordinates) else { continue };
+            let mut tiles = BTreeSet::new();
+            let mut known_bombs = 0;
+            for neighbor in self.tile_map.safe_square_at(*coordinates) {
+                if self.tile_map.tile(neighbor).is_none() {
+                    continue;
+                }
+                if self.bombs.contains(&neighbor) {
+                    known_bombs += self.max_bombs();
+                } else if self.is_unknown(&neighbor) {
+                    tiles.insert(neighbor);
+                }
+            }
+            if tiles.is_empty() {
+                continue;
+            }
+            let constraint = Constraint {
+                tiles,
+                bombs: (count as usize).saturating_sub(known_bombs),
+            };
+            if !constraints.contains(&constraint) {
+                constraints.push(constraint);
+            }
+        }
+        constraints
+    }
+
+    /// A number with no bombs left, or as many bombs left as its covered neighbors can hold
+    fn single_tile_deduction(constraints: &[Constraint], max_bombs: usize) -> Deduction {
+        let mut deduction = Deduction::default();
+        for constraint in constraints {
+            if constraint.bombs == 0 {
+                deduction.safe.extend(constraint.tiles.iter().copied());
+            } else if constraint.bombs == max_bombs * constraint.tiles.len() {
+                deduction.bombs.extend(constraint.tiles.iter().copied());
+            }
+        }
+        deduction
+    }
+
+    /// Compares overlapping constraints:
+    /// - if `a` is a subset of `b` with the same bomb count, the rest of `b` is safe
+    /// - if the extra bombs of `b` fill every tile only `b` sees,
+    ///   those are bombs and the tiles only `a` sees are safe
+    fn constraint_pair_deduction(constraints: &[Constraint], max_bombs: usize) -> Deduction {
+        let mut by_tile: HashMap<Coordinates, Vec<usize>> = HashMap::new();
+        for (index, constraint) in constraints.iter().enumerate() {
+            for tile in &constraint.tiles {
+                by_tile.entry(*tile).or_default().push(index);
+            }
+        }
+
+        let mut deduction = Deduction::default();
+        for (a_index, a) in constraints.iter().enumerate() {
+            let overlapping: BTreeSet<usize> = a
+                .tiles
+                .iter()
+                .flat_map(|tile| by_tile[tile].iter().copied())
+                .filter(|index| *index != a_index)
+                .collect();
+            for b in overlapping.into_iter().map(|index| &constraints[index]) {
+                let only_b: Vec<Coordinates> = b.tiles.difference(&a.tiles).copied().collect();
+                if only_b.is_empty() || b.bombs < a.bombs {
+                    continue;
+                }
+                let extra_bombs = b.bombs - a.bombs;
+                if extra_bombs == 0 && a.tiles.is_subset(&b.tiles) {
+                    deduction.safe.extend(only_b);
+                } else if extra_bombs == max_bombs * only_b.len() {
+                    deduction.bombs.extend(only_b);
+                    deduction.safe.extend(a.tiles.difference(&b.tiles).copied());
+                }
+            }
+        }
+        deduction
+    }
+
+    /// Uses the total bomb count once every bomb is found, or once every unknown tile must be one
+    fn bomb_count_deduction(&self) -> Deduction {
+        let remaining_bombs = (self.tile_map.bomb_count() as usize)
+            .saturating_sub(self.max_bombs() * self.bombs.len());
+        let unknown: BTreeSet<Coordinates> = self
+            .tile_map
+            .coordinates()
+            .filter(|coordinates| self.is_unknown(coordinates))
+            .collect();
+
+        let mut deduction = Deduction::default();
+        if remaining_bombs == 0 {
+            deduction.safe = unknown;
+        } else if remaining_bombs == self.max_bombs() * unknown.len() {
+            deduction.bombs = unknown;
+        }
+        deduction
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn constraint(tiles: &[Coordinates], bombs: usize) -> Constraint {
+        Constraint {
+            tiles: tiles.iter().copied().collect(),
+            bombs,
+        }
+    }
+
+    #[test]
+    fn boards_cleared_by_deductions_are_solvable() {
+        let bombs: Vec<Coordinates> = (0..5).map(|y| Coordinates::at(2, y)).collect();
+        let tile_map = TileMap::empty(5, 5).with_bombs(&bombs);
+        assert!(Solver::is_solvable_from(&tile_map, Coordinates::at(0, 0)));
+    }
+
+    #[test]
+    fn boards_needing_a_guess_are_not_solvable() {
+        // The bomb is either of the two tiles of the right column
+        let tile_map = TileMap::empty(3, 2).with_bombs(&[Coordinates::at(2, 1)]);
+        assert!(!Solver::is_solvable_from(&tile_map, Coordinates::at(0, 0)));
+    }
+
+    #[test]
+    fn starting_on_a_bomb_is_not_solvable() {
+        let tile_map = TileMap::empty(3, 1).with_bombs(&[Coordinates::at(0, 0)]);
+        assert!(!Solver::is_solvable_from(&tile_map, Coordinates::at(0, 0)));
+    }
+
+    #[test]
+    fn single_tiles_deduce_from_full_and_empty_numbers() {
+        let [a, b, c] = [Coordinates::at(0, 0), Coordinates::at(1, 0), Coordinates::at(2, 0)];
+        let deduction = Solver::single_tile_deduction(&[constraint(&[a, b], 2), constraint(&[c], 0)], 1);
+        assert_eq!(deduction.bombs, BTreeSet::from([a, b]));
+        assert_eq!(deduction.safe, BTreeSet::from([c]));
+    }
+
+    #[test]
+    fn single_tiles_deduce_full_tiles_only_with_several_bombs() {
+        let [a, b] = [Coordinates::at(0, 0), Coordinates::at(1, 0)];
+        let deduction = Solver::single_tile_deduction(&[constraint(&[a, b], 2)], 2);
+        assert!(deduction.is_empty());
+        let deduction = Solver::single_tile_deduction(&[constraint(&[a, b], 4)], 2);
+        assert_eq!(deduction.bombs, BTreeSet::from([a, b]));
+    }
+
+    #[test]
+    fn pairs_with_the_same_bombs_clear_the_extra_tiles() {
+        let [a, b, c] = [Coordinates::at(0, 0), Coordinates::at(1, 0), Coordinates::at(2, 0)];
+        let constraints = [constraint(&[a, b], 1), constraint(&[a, b, c], 1)];
+        assert!(Solver::single_tile_deduction(&constraints, 1).is_empty());
+        let deduction = Solver::constraint_pair_deduction(&constraints, 1);
+        assert_eq!(deduction.safe, BTreeSet::from([c]));
+        assert!(deduction.bombs.is_empty());
+    }
+
+    #[test]
+    fn pairs_with_extra_bombs_fill_the_extra_tiles() {
+        // The 1-2 pattern
+        let [a, b, c, d] = [0, 1, 2, 3].map(|x| Coordinates::at(x, 0));
+        let constraints = [constraint(&[a, b, c], 1), constraint(&[b, c, d], 2)];
+        assert!(Solver::single_tile_deduction(&constraints, 1).is_empty());
+        let deduction = Solver::constraint_pair_deduction(&constraints, 1);
+        assert_eq!(deduction.bombs, BTreeSet::from([d]));
+        assert_eq!(deduction.safe, BTreeSet::from([a]));
+    }
+
+    #[test]
+    fn bomb_count_clears_the_board_once_every_bomb_is_found() {
+        let tile_map = TileMap::empty(5, 1).with_bombs(&[Coordinates::at(1, 0)]);
+        let mut solver = Solver::new(&tile_map);
+        solver.uncover(Coordinates::at(0, 0));
+        solver.bombs.insert(Coordinates::at(1, 0));
+        let deduction = solver.deduce();
+        assert_eq!(deduction.safe, (2..5).map(|x| Coordinates::at(x, 0)).collect());
+    }
+
+    #[test]
+    fn bomb_count_fills_the_board_when_only_bombs_are_left() {
+        let tile_map = TileMap::empty(2, 2).with_bombs(&[Coordinates::at(0, 1), Coordinates::at(1, 1)]);
+        let mut solver = Solver::new(&tile_map);
+        solver.uncover(Coordinates::at(0, 0));
+        solver.uncover(Coordinates::at(1, 0));
+        assert!(solver.is_solved());
+        assert_eq!(
+            solver.bomb_count_deduction().bombs,
+            BTreeSet::from([Coordinates::at(0, 1), Coordinates::at(1, 1)])
+        );
+    }
+
+    #[test]
+    fn hint_prefers_safe_tiles() {
+        let bombs: Vec<Coordinates> = (0..5).map(|y| Coordinates::at(2, y)).collect();
+        let mut game = Game::new(TileMap::empty(5, 5).with_bombs(&bombs));
+        game.reveal(Coordinates::at(0, 0));
+        // The bombs of the middle column are deduced first, then the bomb count proves the rest safe
+        assert_eq!(Solver::hint(&game), Some(Hint::Safe(Coordinates::at(3, 0))));
+    }
+
+    #[test]
+    fn hint_points_to_unflagged_bombs() {
+        let tile_map = TileMap::empty(5, 1).with_bombs(&[Coordinates::at(1, 0), Coordinates::at(3, 0)]);
+        let mut game = Game::new(tile_map);
+        assert_eq!(Solver::hint(&game), None);
+        game.reveal(Coordinates::at(0, 0));
+        assert_eq!(Solver::hint(&game), Some(Hint::Bomb(Coordinates::at(1, 0))));
+        game.toggle_flag(Coordinates::at(1, 0));
+        assert_eq!(Solver::hint(&game), None);
+    }
+}
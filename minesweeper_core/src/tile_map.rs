@@ -0,0 +1,543 @@
+use std::collections::BTreeSet;
+use std::ops::{Deref, DerefMut};
+use crate::{BoardMask, Coordinates};
+use log::warn;
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use crate::tile::Tile;
+use crate::topology::{BoardTopology, Neighborhood};
+//https://github.com/leonidv/bevy-minesweeper-tutorial/commit/45e742b4cab3aab62bb263cb3d366ae9ce006c45
+
+//Base tile map
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TileMap {
+    bomb_count: u16,
+    height: u16,
+    width: u16,
+    #[serde(default)]
+    topology: BoardTopology,
+    /// Do neighbors wrap around the map edges, like on a torus?
+    #[serde(default)]
+    wrapping: bool,
+    #[serde(default)]
+    neighborhood: Neighborhood,
+    /// Cells of the rectangle which aren't tiles, shaping the map
+    #[serde(default)]
+    holes: BTreeSet<Coordinates>,
+    /// Most bombs a single tile can hold
+    #[serde(default = "default_max_bombs_per_tile")]
+    max_bombs_per_tile: u8,
+    map: Vec<Vec<Tile>>,
+}
+
+impl TileMap {
+    // Generates an empty map of square tiles
+    pub fn empty(width: u16, height: u16) -> Self {
+        Self::with_topology(width, height, BoardTopology::Square)
+    }
+
+    // Generates an empty map laid out following `topology`
+    pub fn with_topology(width: u16, height: u16, topology: BoardTopology) -> Self {
+        let map = (0..height)
+            .map(|_| (0..width)
+                .map(|_| Tile::Empty)
+                .collect())
+            .collect();
+        Self {
+            bomb_count: 0,
+            height,
+            width,
+            topology,
+            wrapping: false,
+            neighborhood: Neighborhood::Adjacent,
+            holes: BTreeSet::new(),
+            max_bombs_per_tile: 1,
+            map
+        }
+    }
+
+    #[cfg(feature = "debug")]
+    pub fn console_output(&self) -> String {
+        let mut buffer = format!(
+            "Map ({},{}) with {} bombs:\n",
+            self.width, self.height, self.bomb_count
+        );
+        let line: String = (0..=(self.width + 1))
+            .map(|_| '-')
+            .collect();
+        buffer = format!("{}{}\n", buffer, line);
+        for line in self.iter().rev() {
+            buffer = format!("{}|", buffer);
+            for tile in line.iter() {
+                buffer = format!("{}{}", buffer, tile.console_output());
+            }
+            buffer = format!("{}|\n", buffer);
+        }
+        format!("{}{}", buffer, line)
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    pub fn bomb_count(&self) -> u16 {
+        self.bomb_count
+    }
+
+    pub fn max_bombs_per_tile(&self) -> u8 {
+        self.max_bombs_per_tile
+    }
+
+    /// Lets tiles hold up to `max_bombs_per_tile` bombs, numbers then sum the bombs around
+    pub fn set_max_bombs_per_tile(&mut self, max_bombs_per_tile: u8) {
+        self.max_bombs_per_tile = max_bombs_per_tile.max(1);
+    }
+
+    /// Number of tiles holding bombs, less than the bomb count when tiles hold several
+    pub fn bomb_tile_count(&self) -> usize {
+        self.coordinates()
+            .filter(|coordinates| self.is_bomb_at(*coordinates))
+            .count()
+    }
+
+    /// Makes the `mask` holes inside the map holes of the map, which hold no tile
+    pub fn set_mask(&mut self, mask: &BoardMask) {
+        self.holes = mask
+            .holes()
+            .iter()
+            .filter(|hole| hole.coord_x < self.width && hole.coord_y < self.height)
+            .copied()
+            .collect();
+    }
+
+    pub fn is_hole(&self, coordinates: Coordinates) -> bool {
+        self.holes.contains(&coordinates)
+    }
+
+    /// Number of tiles, holes excluded
+    pub fn tile_count(&self) -> usize {
+        self.width as usize * self.height as usize - self.holes.len()
+    }
+
+    /// Coordinates of every tile, row by row from the bottom left corner, holes excluded
+    pub fn coordinates(&self) -> impl Iterator<Item = Coordinates> + '_ {
+        (0..self.height)
+            .flat_map(move |coord_y| {
+                (0..self.width).map(move |coord_x| Coordinates { coord_x, coord_y })
+            })
+            .filter(|coordinates| !self.is_hole(*coordinates))
+    }
+
+    pub fn topology(&self) -> BoardTopology {
+        self.topology
+    }
+
+    pub fn is_wrapping(&self) -> bool {
+        self.wrapping
+    }
+
+    /// Makes neighbors wrap around the map edges.
+    /// Hex maps need an even row count for rows to keep alternating across the edge
+    pub fn set_wrapping(&mut self, wrapping: bool) {
+        self.wrapping = wrapping;
+    }
+
+    pub fn neighborhood(&self) -> &Neighborhood {
+        &self.neighborhood
+    }
+
+    /// Counts the bombs of `neighborhood` instead of the adjacent tiles
+    pub fn set_neighborhood(&mut self, neighborhood: Neighborhood) {
+        self.neighborhood = neighborhood;
+    }
+
+    /// Neighbors of `coordinates` following the map topology and neighborhood.
+    /// Some may be out of the map, unless it wraps
+    pub fn safe_square_at(&self, coordinates: Coordinates) -> impl Iterator<Item = Coordinates> {
+        let offsets = self
+            .neighborhood
+            .offsets(self.topology, coordinates)
+            .iter()
+            .copied();
+        if !self.wrapping {
+            return offsets
+                .map(|offset| coordinates + offset)
+                .collect::<Vec<_>>()
+                .into_iter();
+        }
+        // Narrow maps wrap several offsets to the same tile, or back to the tile itself
+        let mut neighbors: Vec<Coordinates> = offsets
+            .map(|offset| coordinates.wrapping_add(offset, self.width, self.height))
+            .filter(|neighbor| *neighbor != coordinates)
+            .collect();
+        neighbors.sort();
+        neighbors.dedup();
+        neighbors.into_iter()
+    }
+
+    /// Tile at `coordinates`, `None` when out of the map or in a hole
+    pub fn tile(&self, coordinates: Coordinates) -> Option<Tile> {
+        if self.is_hole(coordinates) {
+            return None;
+        }
+        self.map
+            .get(coordinates.coord_y as usize)?
+            .get(coordinates.coord_x as usize)
+            .copied()
+    }
+
+    /// First empty tile, row by row from the bottom left corner
+    pub fn safe_start(&self) -> Option<Coordinates> {
+        self.coordinates()
+            .find(|coordinates| self.tile(*coordinates) == Some(Tile::Empty))
+    }
+
+    pub fn is_bomb_at(&self, coordinates: Coordinates) -> bool {
+        if coordinates.coord_x >= self.width || coordinates.coord_y >=self.height {
+            return false;
+        }
+        self.map[coordinates.coord_y as usize][coordinates.coord_x as usize].is_bomb()
+    }
+
+    pub fn bomb_count_at(&self, coordinates: Coordinates) -> u8 {
+        if self.is_bomb_at(coordinates) {
+            return 0;
+        }
+        let res: u32 = self
+            .safe_square_at(coordinates)
+            .filter_map(|coord| self.tile(coord))
+            .map(|tile| tile.bomb_count() as u32)
+            .sum();
+        res.min(u8::MAX as u32) as u8
+    }
+
+    /// Bechtel's Board Benchmark Value (3BV): the minimum number of clicks clearing the board
+    /// without chording, one per opening plus one per number not bordering an opening
+    pub fn three_bv(&self) -> u32 {
+        let in_map = |c: &Coordinates| c.coord_x < self.width && c.coord_y < self.height;
+        let mut cleared = std::collections::HashSet::new();
+        let mut count = 0;
+        for row in 0..self.height {
+            for column in 0..self.width {
+                let start = Coordinates { coord_x: column, coord_y: row };
+                if self.tile(start) != Some(Tile::Empty) || cleared.contains(&start) {
+                    continue;
+                }
+                // Flood fill the opening, its border numbers are cleared with it
+                count += 1;
+                cleared.insert(start);
+                let mut to_visit = vec![start];
+                while let Some(coordinates) = to_visit.pop() {
+                    for neighbor in self.safe_square_at(coordinates).filter(in_map) {
+                        if cleared.insert(neighbor) && self.tile(neighbor) == Some(Tile::Empty) {
+                            to_visit.push(neighbor);
+                        }
+                    }
+                }
+            }
+        }
+        for row in 0..self.height {
+            for column in 0..self.width {
+                let coordinates = Coordinates { coord_x: column, coord_y: row };
+                if let Some(Tile::BombNeighbor(_)) = self.tile(coordinates) {
+                    if !cleared.contains(&coordinates) {
+                        count += 1;
+                    }
+                }
+            }
+        }
+        count
+    }
+
+    /// Places `bomb_count` bombs using `rng`, the same rng state always yields the same map.
+    /// See `set_bombs_avoiding` for bombs which don't fit
+    pub fn set_bombs<R: RngCore + ?Sized>(&mut self, bomb_count: u16, rng: &mut R) {
+        self.set_bombs_avoiding(bomb_count, rng, &[]);
+    }
+
+    /// Places `bomb_count` bombs using `rng`, leaving the `safe_area` tiles bomb free.
+    /// The bomb count is clamped to what the other tiles can hold, `bomb_count()` returns the placed bombs
+    pub fn set_bombs_avoiding<R: RngCore + ?Sized>(
+        &mut self,
+        bomb_count: u16,
+        rng: &mut R,
+        safe_area: &[Coordinates],
+    ) {
+        let capacity = self.bomb_capacity(safe_area);
+        let bomb_count = if bomb_count as usize > capacity {
+            warn!("{} bombs don't fit on the map, placing {}", bomb_count, capacity);
+            capacity as u16
+        } else {
+            bomb_count
+        };
+        self.bomb_count = bomb_count;
+        let mut remaining_bombs = bomb_count;
+        // Place bombs
+        while remaining_bombs > 0 {
+            let (x, y) = (
+                rng.gen_range(0..self.width) as usize,
+                rng.gen_range(0..self.height) as usize,
+            );
+            let coordinates = Coordinates { coord_x: x as u16, coord_y: y as u16 };
+            if safe_area.contains(&coordinates) || self.is_hole(coordinates) {
+                continue;
+            }
+            match self[y][x] {
+                Tile::Empty => self[y][x] = Tile::Bomb(1),
+                Tile::Bomb(count) if count < self.max_bombs_per_tile => {
+                    self[y][x] = Tile::Bomb(count + 1)
+                }
+                _ => continue,
+            }
+            remaining_bombs -= 1;
+        }
+        self.set_bomb_neighbors();
+    }
+
+    /// Bombs the tiles out of `safe_area` can still hold
+    fn bomb_capacity(&self, safe_area: &[Coordinates]) -> usize {
+        self.coordinates()
+            .filter(|coordinates| !safe_area.contains(coordinates))
+            .map(|coordinates| {
+                let bomb_count = self.tile(coordinates).map_or(0, |tile| tile.bomb_count());
+                self.max_bombs_per_tile.saturating_sub(bomb_count) as usize
+            })
+            .sum()
+    }
+
+    /// Numbers the tiles around the bombs
+    fn set_bomb_neighbors(&mut self) {
+        /*
+            The row number is a y coordinate, and the column number is an x coordinate,
+            Confusing since normally you write [row (y)][col (x)]
+            but we also usually write (x,y).
+        */
+        for row in 0..self.height {
+            for column in 0..self.width {
+                let coords = Coordinates { coord_x: column, coord_y: row, };
+                if self.is_bomb_at(coords) || self.is_hole(coords) {
+                    continue;
+                }
+                let number_of_bombs = self.bomb_count_at(coords);
+                if number_of_bombs == 0 {
+                    continue;
+                }
+                let tile = &mut self[row as usize][column as usize];
+                *tile = Tile::BombNeighbor(number_of_bombs);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+impl TileMap {
+    /// Places a bomb on each of `bombs` instead of random ones, a tile listed twice holds two
+    pub(crate) fn with_bombs(mut self, bombs: &[Coordinates]) -> Self {
+        for coordinates in bombs {
+            let tile = &mut self[coordinates.coord_y as usize][coordinates.coord_x as usize];
+            *tile = Tile::Bomb(tile.bomb_count() + 1);
+        }
+        self.bomb_count = bombs.len() as u16;
+        self.set_bomb_neighbors();
+        self
+    }
+}
+
+fn default_max_bombs_per_tile() -> u8 {
+    1
+}
+
+impl Deref for TileMap {
+    type Target = Vec<Vec<Tile>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.map
+    }
+}
+
+impl DerefMut for TileMap {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.map
+    }
+}
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use super::*;
+
+    fn bombs_of(tile_map: &TileMap) -> Vec<Coordinates> {
+        (0..tile_map.height())
+            .flat_map(|coord_y| (0..tile_map.width()).map(move |coord_x| Coordinates { coord_x, coord_y }))
+            .filter(|c| tile_map.is_bomb_at(*c))
+            .collect()
+    }
+
+    #[test]
+    fn numbers_count_the_adjacent_bombs() {
+        let tile_map = TileMap::empty(3, 3).with_bombs(&[Coordinates::at(0, 0), Coordinates::at(2, 2)]);
+        assert_eq!(tile_map.tile(Coordinates::at(1, 1)), Some(Tile::BombNeighbor(2)));
+        assert_eq!(tile_map.tile(Coordinates::at(1, 0)), Some(Tile::BombNeighbor(1)));
+        assert_eq!(tile_map.tile(Coordinates::at(2, 0)), Some(Tile::Empty));
+        assert_eq!(tile_map.tile(Coordinates::at(0, 0)), Some(Tile::Bomb(1)));
+        assert_eq!(tile_map.bomb_count_at(Coordinates::at(0, 0)), 0);
+    }
+
+    #[test]
+    fn tiles_out_of_the_map_are_none() {
+        let tile_map = TileMap::empty(3, 2);
+        assert_eq!(tile_map.tile(Coordinates::at(3, 0)), None);
+        assert_eq!(tile_map.tile(Coordinates::at(0, 2)), None);
+        assert!(!tile_map.is_bomb_at(Coordinates::at(5, 5)));
+    }
+
+    #[test]
+    fn same_seed_places_the_same_bombs() {
+        let mut first = TileMap::empty(10, 10);
+        first.set_bombs(20, &mut StdRng::seed_from_u64(42));
+        let mut second = TileMap::empty(10, 10);
+        second.set_bombs(20, &mut StdRng::seed_from_u64(42));
+        assert_eq!(bombs_of(&first).len(), 20);
+        assert_eq!(bombs_of(&first), bombs_of(&second));
+    }
+
+    #[test]
+    fn safe_area_holds_no_bomb() {
+        let safe_area = [Coordinates::at(0, 0), Coordinates::at(1, 0), Coordinates::at(0, 1), Coordinates::at(1, 1)];
+        for seed in 0..20 {
+            let mut tile_map = TileMap::empty(4, 4);
+            tile_map.set_bombs_avoiding(12, &mut StdRng::seed_from_u64(seed), &safe_area);
+            assert_eq!(bombs_of(&tile_map).len(), 12);
+            assert!(safe_area.iter().all(|c| !tile_map.is_bomb_at(*c)));
+        }
+    }
+
+    #[test]
+    fn bombs_not_fitting_are_dropped() {
+        let mut tile_map = TileMap::empty(3, 3);
+        tile_map.set_bombs_avoiding(20, &mut StdRng::seed_from_u64(0), &[Coordinates::at(1, 1)]);
+        assert_eq!(tile_map.bomb_count(), 8);
+        assert_eq!(bombs_of(&tile_map).len(), 8);
+        assert!(!tile_map.is_bomb_at(Coordinates::at(1, 1)));
+    }
+
+    #[test]
+    fn safe_start_is_the_first_empty_tile() {
+        let tile_map = TileMap::empty(3, 3).with_bombs(&[Coordinates::at(0, 0)]);
+        assert_eq!(tile_map.safe_start(), Some(Coordinates::at(2, 0)));
+        let full = TileMap::empty(2, 1).with_bombs(&[Coordinates::at(0, 0)]);
+        assert_eq!(full.safe_start(), None);
+    }
+
+    #[test]
+    fn three_bv_counts_openings_and_isolated_numbers() {
+        // `. 1 * 1 .`: an opening on each side, clearing the number it borders
+        let tile_map = TileMap::empty(5, 1).with_bombs(&[Coordinates::at(2, 0)]);
+        assert_eq!(tile_map.three_bv(), 2);
+        // `* 2 *`: a single number bordering no opening
+        let tile_map = TileMap::empty(3, 1).with_bombs(&[Coordinates::at(0, 0), Coordinates::at(2, 0)]);
+        assert_eq!(tile_map.three_bv(), 1);
+    }
+
+    #[test]
+    fn wrapping_neighbors_cross_the_edges() {
+        let mut tile_map = TileMap::empty(4, 4);
+        tile_map.set_wrapping(true);
+        let neighbors: BTreeSet<Coordinates> = tile_map.safe_square_at(Coordinates::at(0, 0)).collect();
+        assert_eq!(neighbors.len(), 8);
+        assert!(neighbors.contains(&Coordinates::at(3, 3)));
+        assert!(neighbors.contains(&Coordinates::at(3, 0)));
+        assert!(neighbors.contains(&Coordinates::at(0, 3)));
+    }
+
+    #[test]
+    fn narrow_wrapping_maps_list_neighbors_once() {
+        let mut tile_map = TileMap::empty(2, 2);
+        tile_map.set_wrapping(true);
+        let neighbors: Vec<Coordinates> = tile_map.safe_square_at(Coordinates::at(0, 0)).collect();
+        assert_eq!(neighbors, vec![Coordinates::at(1, 0), Coordinates::at(0, 1), Coordinates::at(1, 1)]);
+    }
+
+    #[test]
+    fn wrapping_numbers_count_bombs_across_the_edges() {
+        let mut tile_map = TileMap::empty(4, 4);
+        tile_map.set_wrapping(true);
+        let tile_map = tile_map.with_bombs(&[Coordinates::at(3, 3)]);
+        assert_eq!(tile_map.tile(Coordinates::at(0, 0)), Some(Tile::BombNeighbor(1)));
+    }
+
+    #[test]
+    fn custom_neighborhoods_change_the_numbers() {
+        let mut tile_map = TileMap::empty(3, 3);
+        tile_map.set_neighborhood(Neighborhood::knight());
+        let tile_map = tile_map.with_bombs(&[Coordinates::at(0, 0)]);
+        assert_eq!(tile_map.tile(Coordinates::at(1, 2)), Some(Tile::BombNeighbor(1)));
+        assert_eq!(tile_map.tile(Coordinates::at(2, 1)), Some(Tile::BombNeighbor(1)));
+        assert_eq!(tile_map.tile(Coordinates::at(1, 1)), Some(Tile::Empty));
+    }
+
+    #[test]
+    fn holes_hold_neither_tiles_nor_bombs() {
+        let mask: BoardMask = "#.#\n###".parse().unwrap();
+        let mut tile_map = TileMap::empty(3, 2);
+        tile_map.set_mask(&mask);
+        let hole = Coordinates::at(1, 1);
+        assert!(tile_map.is_hole(hole));
+        assert_eq!(tile_map.tile(hole), None);
+        assert_eq!(tile_map.tile_count(), 5);
+        assert!(!tile_map.coordinates().any(|c| c == hole));
+        for seed in 0..20 {
+            let mut tile_map = tile_map.clone();
+            tile_map.set_bombs(4, &mut StdRng::seed_from_u64(seed));
+            assert!(!tile_map.is_bomb_at(hole));
+            assert_eq!(bombs_of(&tile_map).len(), 4);
+        }
+    }
+
+    #[test]
+    fn bombs_not_fitting_around_holes_are_clamped() {
+        let mask: BoardMask = "#.#\n###".parse().unwrap();
+        let mut tile_map = TileMap::empty(3, 2);
+        tile_map.set_mask(&mask);
+        tile_map.set_bombs(6, &mut StdRng::seed_from_u64(1));
+        assert_eq!(tile_map.bomb_count(), 5);
+        assert_eq!(bombs_of(&tile_map).len(), 5);
+    }
+
+    #[test]
+    fn tiles_hold_up_to_the_max_bombs() {
+        for seed in 0..20 {
+            let mut tile_map = TileMap::empty(3, 3);
+            tile_map.set_max_bombs_per_tile(3);
+            tile_map.set_bombs(20, &mut StdRng::seed_from_u64(seed));
+            let bombs: u32 = tile_map.coordinates().map(|c| tile_map.tile(c).unwrap().bomb_count() as u32).sum();
+            assert_eq!(bombs, 20);
+            assert!(tile_map.coordinates().all(|c| tile_map.tile(c).unwrap().bomb_count() <= 3));
+        }
+    }
+
+    #[test]
+    fn bomb_room_counts_the_max_bombs_per_tile() {
+        let mut tile_map = TileMap::empty(2, 2);
+        tile_map.set_max_bombs_per_tile(3);
+        tile_map.set_bombs(20, &mut StdRng::seed_from_u64(1));
+        assert_eq!(tile_map.bomb_count(), 12);
+        assert!(tile_map.coordinates().all(|c| tile_map.tile(c) == Some(Tile::Bomb(3))));
+    }
+
+    #[test]
+    fn numbers_sum_the_bomb_quantities() {
+        let mut tile_map = TileMap::empty(3, 1);
+        tile_map.set_max_bombs_per_tile(3);
+        let tile_map = tile_map.with_bombs(&[Coordinates::at(0, 0), Coordinates::at(0, 0), Coordinates::at(2, 0)]);
+        assert_eq!(tile_map.tile(Coordinates::at(1, 0)), Some(Tile::BombNeighbor(3)));
+        assert_eq!(tile_map.bomb_tile_count(), 2);
+        assert_eq!(tile_map.bomb_count(), 3);
+    }
+}
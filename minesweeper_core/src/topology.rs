@@ -0,0 +1,183 @@
+use serde::{Deserialize, Serialize};
+use crate::Coordinates;
+
+/// Delta coordinates for all 8 square neighbors
+/*
+*--------*-------*-------*
+| -1, 1  | 0, 1  | 1, 1  |
+|--------|-------|-------|
+| -1, 0  | tile  | 1, 0  |
+|--------|-------|-------|
+| -1, -1 | 0, -1 | 1, -1 |
+*--------*-------*-------*
+*/
+const SQUARE_COORDINATES: [(i8, i8); 8] = [
+    (-1, -1), (0, -1), (1, -1),     //bottom left, bottom, bottom right
+    (-1,  0),          (1,  0),     //Left, Right
+    (-1,  1), (0,  1), (1,  1),      //top left, top, top right
+];
+
+/// Delta coordinates for the 6 hex neighbors of a tile on an even row.
+/// Odd rows are shifted half a tile right
+const HEX_EVEN_ROW_COORDINATES: [(i8, i8); 6] = [
+    (-1, -1), (0, -1),              //bottom left, bottom right
+    (-1,  0), (1,  0),              //Left, Right
+    (-1,  1), (0,  1),              //top left, top right
+];
+
+/// Delta coordinates for the 6 hex neighbors of a tile on an odd row
+const HEX_ODD_ROW_COORDINATES: [(i8, i8); 6] = [
+    (0, -1), (1, -1),               //bottom left, bottom right
+    (-1, 0), (1,  0),               //Left, Right
+    (0,  1), (1,  1),               //top left, top right
+];
+
+/// How the tiles of a map are laid out, and so which tiles are neighbors
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BoardTopology {
+    /// Square tiles, with 8 neighbors
+    #[default]
+    Square,
+    /// Pointy top hexagons with 6 neighbors, odd rows shifted half a tile right
+    Hex,
+}
+
+impl BoardTopology {
+    /// Delta coordinates of the neighbors of `coordinates`
+    pub fn neighbor_offsets(&self, coordinates: Coordinates) -> &'static [(i8, i8)] {
+        match self {
+            BoardTopology::Square => &SQUARE_COORDINATES,
+            BoardTopology::Hex if coordinates.coord_y.is_multiple_of(2) => &HEX_EVEN_ROW_COORDINATES,
+            BoardTopology::Hex => &HEX_ODD_ROW_COORDINATES,
+        }
+    }
+
+    /// Neighbors of a tile away from the map edges
+    pub fn neighbor_count(&self) -> usize {
+        match self {
+            BoardTopology::Square => SQUARE_COORDINATES.len(),
+            BoardTopology::Hex => HEX_EVEN_ROW_COORDINATES.len(),
+        }
+    }
+}
+
+/// Tiles a number counts the bombs of, also revealed around empty tiles and by chording
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Neighborhood {
+    /// Tiles touching each other in the board topology
+    #[default]
+    Adjacent,
+    /// Tiles at the given delta coordinates, on square boards
+    Offsets(Vec<(i8, i8)>),
+}
+
+impl Neighborhood {
+    /// The 4 tiles sharing a side
+    pub fn orthogonal() -> Self {
+        Self::Offsets(vec![(0, -1), (-1, 0), (1, 0), (0, 1)])
+    }
+
+    /// The 8 tiles a chess knight moves to
+    pub fn knight() -> Self {
+        Self::Offsets(vec![
+            (-1, -2), (1, -2),
+            (-2, -1), (2, -1),
+            (-2,  1), (2,  1),
+            (-1,  2), (1,  2),
+        ])
+    }
+
+    /// Every tile at most `radius` tiles away in both directions
+    pub fn radius(radius: i8) -> Self {
+        Self::Offsets(
+            (-radius..=radius)
+                .flat_map(|y| (-radius..=radius).map(move |x| (x, y)))
+                .filter(|offset| *offset != (0, 0))
+                .collect(),
+        )
+    }
+
+    /// Delta coordinates of the neighbors of `coordinates` on a `topology` map
+    pub fn offsets(&self, topology: BoardTopology, coordinates: Coordinates) -> &[(i8, i8)] {
+        match self {
+            Neighborhood::Adjacent => topology.neighbor_offsets(coordinates),
+            Neighborhood::Offsets(offsets) => offsets,
+        }
+    }
+
+    /// Neighbors of a tile away from the map edges
+    pub fn neighbor_count(&self, topology: BoardTopology) -> usize {
+        match self {
+            Neighborhood::Adjacent => topology.neighbor_count(),
+            Neighborhood::Offsets(offsets) => offsets.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tile::Tile;
+    use crate::tile_map::TileMap;
+
+    fn neighbor(coordinates: Coordinates, (x, y): (i8, i8)) -> Coordinates {
+        Coordinates {
+            coord_x: (coordinates.coord_x as i16 + x as i16) as u16,
+            coord_y: (coordinates.coord_y as i16 + y as i16) as u16,
+        }
+    }
+
+    #[test]
+    fn hex_neighbors_are_mutual() {
+        for coord_y in 1..5 {
+            for coord_x in 1..5 {
+                let coordinates = Coordinates { coord_x, coord_y };
+                for offset in BoardTopology::Hex.neighbor_offsets(coordinates) {
+                    let other = neighbor(coordinates, *offset);
+                    assert!(BoardTopology::Hex
+                        .neighbor_offsets(other)
+                        .iter()
+                        .any(|back| neighbor(other, *back) == coordinates));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn neighbor_counts_match_the_offsets() {
+        let coordinates = Coordinates::at(3, 3);
+        for topology in [BoardTopology::Square, BoardTopology::Hex] {
+            assert_eq!(topology.neighbor_count(), topology.neighbor_offsets(coordinates).len());
+            assert_eq!(
+                Neighborhood::Adjacent.offsets(topology, coordinates),
+                topology.neighbor_offsets(coordinates)
+            );
+        }
+        assert_eq!(BoardTopology::Square.neighbor_count(), 8);
+        assert_eq!(BoardTopology::Hex.neighbor_count(), 6);
+        assert_eq!(Neighborhood::orthogonal().neighbor_count(BoardTopology::Square), 4);
+        assert_eq!(Neighborhood::knight().neighbor_count(BoardTopology::Square), 8);
+        assert_eq!(Neighborhood::radius(1).neighbor_count(BoardTopology::Square), 8);
+        assert_eq!(Neighborhood::radius(2).neighbor_count(BoardTopology::Square), 24);
+    }
+
+    #[test]
+    fn offsets_are_unique_and_exclude_the_tile() {
+        let coordinates = Coordinates::at(3, 3);
+        for neighborhood in [Neighborhood::orthogonal(), Neighborhood::knight(), Neighborhood::radius(2)] {
+            let offsets = neighborhood.offsets(BoardTopology::Square, coordinates);
+            assert!(!offsets.contains(&(0, 0)));
+            let unique: std::collections::HashSet<_> = offsets.iter().collect();
+            assert_eq!(unique.len(), offsets.len());
+        }
+    }
+
+    #[test]
+    fn hex_numbers_count_the_six_neighbors() {
+        // Bombs all around (1, 1), on an odd row (0, 0) and (0, 2) aren't its neighbors
+        let bombs = [(1, 0), (2, 0), (0, 1), (2, 1), (1, 2), (2, 2)].map(|(x, y)| Coordinates::at(x, y));
+        let tile_map = TileMap::with_topology(3, 3, BoardTopology::Hex).with_bombs(&bombs);
+        assert_eq!(tile_map.tile(Coordinates::at(1, 1)), Some(Tile::BombNeighbor(6)));
+        assert_eq!(tile_map.tile(Coordinates::at(0, 0)), Some(Tile::BombNeighbor(2)));
+    }
+}
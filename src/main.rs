@@ -2,28 +2,36 @@ use bevy::{prelude::*, window::WindowResolution};
 use bevy::color::palettes::tailwind;
 use bevy::log::LogPlugin;
 use board_plugin::BoardPlugin;
-use board_plugin::resources::{BoardAssets, BoardOptions, BoardSize, SpriteMaterial};
-use board_plugin::resources::TileSize::Fixed;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use board_plugin::resources::{seeded_std_rng, BoardAssets, BoardGeneration, BoardOptions, BoardSize, BoardTopology, FirstClick, Neighborhood, SpriteMaterial};
+use board_plugin::resources::TileSize;
+
+mod menu;
 
 #[cfg_attr(feature = "debug", derive(Reflect))]
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, States)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, States, Serialize, Deserialize)]
 pub enum AppState {
     #[default] 
     Setup,
+    Menu,
     NewGame,
     InGame,
     Pause,
-    EndGame
+    EndGame,
+    Won,
 }
 
-fn transition_to_in_game(mut app_state: ResMut<NextState<AppState>>) {
-    app_state.set(AppState::InGame);
+fn transition_to_menu(mut app_state: ResMut<NextState<AppState>>) {
+    app_state.set(AppState::Menu);
 }
 
 fn main() {
-    let mut primary_window = Window::default();
-    primary_window.resolution = WindowResolution::new(850.0, 850.0);
-    primary_window.title = "Mine Sweeper!".to_string();
+    let primary_window = Window {
+        resolution: WindowResolution::new(850.0, 850.0),
+        title: "Mine Sweeper!".to_string(),
+        ..Default::default()
+    };
     App::new()
     .add_plugins(
         DefaultPlugins
@@ -38,14 +46,24 @@ fn main() {
     )
     // https://bevyengine.org/learn/migration-guides/0-13-to-0-14/#onenter-state-schedules-now-run-before-startup-schedules
     .init_state::<AppState>()
-    .add_systems(OnEnter(AppState::Setup), (setup_camera, setup_board))
+    .add_systems(OnEnter(AppState::Setup), (setup_camera, setup_board, menu::init_custom_board).chain())
     .add_systems(Update, 
-                 transition_to_in_game
+                 transition_to_menu
                      .run_if(in_state(AppState::Setup)))
+    .add_systems(OnEnter(AppState::Menu), (BoardPlugin::<AppState>::clear_board, menu::spawn_menu).chain())
+    .add_systems(OnExit(AppState::Menu), menu::despawn_menu)
+    .add_systems(Update, (menu::menu_buttons, menu::menu_keyboard, menu::update_custom_form).run_if(in_state(AppState::Menu)))
+    .add_systems(Update, menu::board_config_error.run_if(in_state(AppState::InGame)))
+    .add_systems(Update,
+                 menu::back_to_menu
+                     .run_if(in_state(AppState::Pause)
+                         .or_else(in_state(AppState::EndGame))
+                         .or_else(in_state(AppState::Won))))
     .add_plugins(BoardPlugin {
         game_state: AppState::InGame,
         pause_state: AppState::Pause,
         game_over_state: AppState::EndGame,
+        won_state: AppState::Won,
     })    
     .run();
 }
@@ -60,9 +78,11 @@ fn setup_board(mut commands: Commands, asset_server: Res<AssetServer>) {
         board_material: SpriteMaterial::color(Color::from(tailwind::NEUTRAL_50)),
         tile_material: SpriteMaterial::color(Color::from(tailwind::STONE_400)),
         covered_tile_material: SpriteMaterial::color(Color::from(tailwind::STONE_300)),
+        hex_tile_texture: asset_server.load("sprites/hex.png"),
         bomb_counter_font: asset_server.load("fonts/pixeled.ttf"),
         bomb_counter_colors: BoardAssets::default_colors(),
         flag_material: SpriteMaterial::texture(&asset_server.load("sprites/flag.png")),
+        question_material: SpriteMaterial::texture(&asset_server.load("sprites/question.png")),
         bomb_material: SpriteMaterial::texture(&asset_server.load("sprites/bomb.png")),
         menu_font: asset_server.load("fonts/neuropol_x_rg.otf"),
     });
@@ -70,11 +90,25 @@ fn setup_board(mut commands: Commands, asset_server: Res<AssetServer>) {
         map_size: BoardSize { columns: 20, rows: 20 },
         bomb_count: 60,
         position: board_plugin::resources::BoardPosition::Centered { offset: Vec3::ZERO },
-        tile_size: Fixed(35.0),
+        tile_size: TileSize::Adaptive { min: 10.0, max: 50.0 },
         tile_padding: 1.5,
+        topology: BoardTopology::Square,
+        wrapping: false,
+        neighborhood: Neighborhood::Adjacent,
+        mask: None,
+        max_bombs_per_tile: 1,
         safe_start: true,
+        first_click: FirstClick::Unprotected,
+        question_marks: false,
+        seed: None,
+        rng_source: seeded_std_rng,
+        generation: BoardGeneration::Random,
+        save_path: Some(PathBuf::from("minesweeper_save.ron")),
+        stats_path: Some(PathBuf::from("minesweeper_stats.ron")),
+        replay_path: Some(PathBuf::from("minesweeper_replay.ron")),
         game_state: AppState::InGame,
         pause_state: AppState::Pause,
         game_over_state: AppState::EndGame,
+        won_state: AppState::Won,
     })
 }
@@ -0,0 +1,412 @@
+use bevy::color::palettes::tailwind;
+use bevy::prelude::*;
+use board_plugin::events::BoardConfigErrorEvent;
+use board_plugin::resources::{BoardAssets, BoardConfigError, BoardOptions, BoardPreset, BoardSize, GameStats, Replay, ReplayPlayer, StatsKey};
+use board_plugin::BoardPlugin;
+use crate::AppState;
+
+const MIN_SIDE: u16 = 2;
+const MAX_SIDE: u16 = 100;
+
+const BUTTON_COLOR: Srgba = tailwind::STONE_700;
+const HOVERED_BUTTON_COLOR: Srgba = tailwind::STONE_500;
+const FOCUS_COLOR: Srgba = tailwind::YELLOW_200;
+
+/// Root of the main menu UI
+#[derive(Component)]
+pub struct MainMenu;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CustomField {
+    Columns,
+    Rows,
+    Bombs,
+}
+
+#[derive(Component, Debug, Clone, Copy)]
+pub enum MenuButton {
+    Continue,
+    WatchReplay,
+    Preset(BoardPreset),
+    Adjust(CustomField, i32),
+    StartCustom,
+}
+
+/// Button focused with the keyboard, drawn with an outline
+#[derive(Component)]
+pub struct MenuFocus;
+
+/// Displays the value of a custom board field
+#[derive(Component)]
+pub struct CustomFieldText(CustomField);
+
+/// Displays why the custom board can't be played
+#[derive(Component)]
+pub struct CustomErrorText;
+
+/// Error of the last board creation, shown in the menu
+#[derive(Debug, Clone, Resource)]
+pub struct BoardErrorMessage(String);
+
+/// Custom board being edited in the menu
+#[derive(Debug, Clone, Resource)]
+pub struct CustomBoard {
+    pub columns: u16,
+    pub rows: u16,
+    pub bomb_count: u16,
+}
+
+impl CustomBoard {
+    fn value(&self, field: CustomField) -> u16 {
+        match field {
+            CustomField::Columns => self.columns,
+            CustomField::Rows => self.rows,
+            CustomField::Bombs => self.bomb_count,
+        }
+    }
+
+    fn adjust(&mut self, field: CustomField, delta: i32) {
+        let (value, min, max) = match field {
+            CustomField::Columns => (&mut self.columns, MIN_SIDE, MAX_SIDE),
+            CustomField::Rows => (&mut self.rows, MIN_SIDE, MAX_SIDE),
+            CustomField::Bombs => (&mut self.bomb_count, 1, u16::MAX),
+        };
+        *value = (*value as i32 + delta).clamp(min as i32, max as i32) as u16;
+    }
+
+    fn map_size(&self) -> BoardSize {
+        BoardSize {
+            columns: self.columns,
+            rows: self.rows,
+        }
+    }
+
+    /// Board options playing this board, if they are valid
+    fn board_options(
+        &self,
+        options: &BoardOptions<AppState>,
+    ) -> Result<BoardOptions<AppState>, BoardConfigError> {
+        let mut options = options.clone();
+        options.map_size = self.map_size();
+        options.bomb_count = self.bomb_count;
+        options.mask = None;
+        options.validate()?;
+        Ok(options)
+    }
+
+    fn error_message(&self, options: &BoardOptions<AppState>) -> String {
+        self.board_options(options)
+            .err()
+            .map(|error| format!("Invalid board: {}", error))
+            .unwrap_or_default()
+    }
+}
+
+pub fn init_custom_board(mut commands: Commands, board_options: Res<BoardOptions<AppState>>) {
+    commands.insert_resource(CustomBoard {
+        columns: board_options.map_size.columns,
+        rows: board_options.map_size.rows,
+        bomb_count: board_options.bomb_count,
+    });
+}
+
+pub fn spawn_menu(
+    mut commands: Commands,
+    board_assets: Res<BoardAssets>,
+    board_options: Res<BoardOptions<AppState>>,
+    custom_board: Res<CustomBoard>,
+    game_stats: Option<Res<GameStats>>,
+    board_error_message: Option<Res<BoardErrorMessage>>,
+) {
+    let title_style = TextStyle {
+        font: board_assets.menu_font.clone(),
+        font_size: 48.0,
+        color: Color::from(tailwind::YELLOW_200),
+    };
+    let text_style = TextStyle {
+        font: board_assets.menu_font.clone(),
+        font_size: 24.0,
+        color: Color::from(tailwind::NEUTRAL_50),
+    };
+    let has_save = board_options
+        .save_path
+        .as_ref()
+        .is_some_and(|path| path.exists());
+    let has_replay = board_options
+        .replay_path
+        .as_ref()
+        .is_some_and(|path| path.exists());
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(12.0),
+                ..Default::default()
+            },
+            background_color: BackgroundColor(Color::from(tailwind::STONE_900)),
+            ..Default::default()
+        })
+        .insert(Name::new("Main menu"))
+        .insert(MainMenu)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section("Mine Sweeper!", title_style));
+            if has_save {
+                spawn_button(parent, "Continue", MenuButton::Continue, &text_style);
+            }
+            if has_replay {
+                spawn_button(parent, "Watch last game", MenuButton::WatchReplay, &text_style);
+            }
+            for preset in BoardPreset::ALL {
+                let size = preset.map_size();
+                let key = StatsKey {
+                    columns: size.columns,
+                    rows: size.rows,
+                    bomb_count: preset.bomb_count(),
+                };
+                let best_time = game_stats
+                    .as_ref()
+                    .and_then(|stats| stats.get(&key))
+                    .and_then(|stats| stats.best_time)
+                    .map(|time| format!(" - best {:.1}s", time.as_secs_f32()))
+                    .unwrap_or_default();
+                let label = format!(
+                    "{} {}x{}, {} mines{}",
+                    preset.name(),
+                    size.columns,
+                    size.rows,
+                    preset.bomb_count(),
+                    best_time
+                );
+                spawn_button(parent, &label, MenuButton::Preset(preset), &text_style);
+            }
+            for (field, name) in [
+                (CustomField::Columns, "Columns"),
+                (CustomField::Rows, "Rows"),
+                (CustomField::Bombs, "Mines"),
+            ] {
+                spawn_custom_field(parent, field, name, custom_board.value(field), &text_style);
+            }
+            let error = match board_error_message {
+                Some(message) => message.0.clone(),
+                None => custom_board.error_message(&board_options),
+            };
+            parent
+                .spawn(TextBundle::from_section(
+                    error,
+                    TextStyle {
+                        color: Color::from(tailwind::RED_400),
+                        ..text_style.clone()
+                    },
+                ))
+                .insert(CustomErrorText);
+            spawn_button(parent, "Start custom game", MenuButton::StartCustom, &text_style);
+        });
+}
+
+fn spawn_button(parent: &mut ChildBuilder, label: &str, button: MenuButton, text_style: &TextStyle) {
+    parent
+        .spawn(ButtonBundle {
+            style: Style {
+                padding: UiRect::axes(Val::Px(16.0), Val::Px(8.0)),
+                justify_content: JustifyContent::Center,
+                ..Default::default()
+            },
+            background_color: BackgroundColor(Color::from(BUTTON_COLOR)),
+            ..Default::default()
+        })
+        .insert(button)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(label, text_style.clone()));
+        });
+}
+
+fn spawn_custom_field(
+    parent: &mut ChildBuilder,
+    field: CustomField,
+    name: &str,
+    value: u16,
+    text_style: &TextStyle,
+) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(8.0),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(name, text_style.clone()));
+            spawn_button(parent, "-10", MenuButton::Adjust(field, -10), text_style);
+            spawn_button(parent, "-1", MenuButton::Adjust(field, -1), text_style);
+            parent
+                .spawn(TextBundle::from_section(value.to_string(), text_style.clone()))
+                .insert(CustomFieldText(field));
+            spawn_button(parent, "+1", MenuButton::Adjust(field, 1), text_style);
+            spawn_button(parent, "+10", MenuButton::Adjust(field, 10), text_style);
+        });
+}
+
+pub fn despawn_menu(mut commands: Commands, menu_query: Query<Entity, With<MainMenu>>) {
+    for entity in menu_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<BoardErrorMessage>();
+}
+
+/// Goes back to the menu when the board options were rejected
+pub fn board_config_error(
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut board_config_error_event_reader: EventReader<BoardConfigErrorEvent>,
+) {
+    for event in board_config_error_event_reader.read() {
+        commands.insert_resource(BoardErrorMessage(format!("Invalid board: {}", event.error)));
+        next_state.set(AppState::Menu);
+    }
+}
+
+pub fn menu_buttons(
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut board_options: ResMut<BoardOptions<AppState>>,
+    mut custom_board: ResMut<CustomBoard>,
+    mut interaction_query: Query<
+        (&Interaction, &MenuButton, &mut BackgroundColor),
+        Changed<Interaction>,
+    >,
+) {
+    for (interaction, button, mut background_color) in interaction_query.iter_mut() {
+        match interaction {
+            Interaction::Hovered => *background_color = BackgroundColor(Color::from(HOVERED_BUTTON_COLOR)),
+            Interaction::None => *background_color = BackgroundColor(Color::from(BUTTON_COLOR)),
+            Interaction::Pressed => press_button(
+                *button,
+                &mut commands,
+                &mut next_state,
+                &mut board_options,
+                &mut custom_board,
+            ),
+        }
+    }
+}
+
+/// Moves the focus between the menu buttons in reading order with the arrows and Tab,
+/// and presses the focused button with Enter or Space
+pub fn menu_keyboard(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut board_options: ResMut<BoardOptions<AppState>>,
+    mut custom_board: ResMut<CustomBoard>,
+    button_query: Query<(Entity, &MenuButton, &GlobalTransform, Has<MenuFocus>)>,
+) {
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let tab = keys.just_pressed(KeyCode::Tab);
+    let step = if keys.any_just_pressed([KeyCode::ArrowDown, KeyCode::ArrowRight]) || (tab && !shift) {
+        1
+    } else if keys.any_just_pressed([KeyCode::ArrowUp, KeyCode::ArrowLeft]) || (tab && shift) {
+        -1
+    } else {
+        0
+    };
+    let mut buttons: Vec<_> = button_query.iter().collect();
+    // UI positions grow downwards, rows of buttons share their y
+    buttons.sort_by(|(_, _, a, _), (_, _, b, _)| {
+        let (a, b) = (a.translation(), b.translation());
+        a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x))
+    });
+    let focused = buttons.iter().position(|(_, _, _, focused)| *focused);
+
+    if step != 0 && !buttons.is_empty() {
+        let next = match focused {
+            Some(index) => (index as i32 + step).rem_euclid(buttons.len() as i32) as usize,
+            None if step > 0 => 0,
+            None => buttons.len() - 1,
+        };
+        if let Some(index) = focused {
+            commands.entity(buttons[index].0).remove::<(MenuFocus, Outline)>();
+        }
+        commands.entity(buttons[next].0).insert((
+            MenuFocus,
+            Outline::new(Val::Px(2.0), Val::ZERO, Color::from(FOCUS_COLOR)),
+        ));
+        return;
+    }
+    if !keys.any_just_pressed([KeyCode::Enter, KeyCode::NumpadEnter, KeyCode::Space]) {
+        return;
+    }
+    let Some(index) = focused else { return };
+    press_button(*buttons[index].1, &mut commands, &mut next_state, &mut board_options, &mut custom_board);
+}
+
+fn press_button(
+    button: MenuButton,
+    commands: &mut Commands,
+    next_state: &mut NextState<AppState>,
+    board_options: &mut BoardOptions<AppState>,
+    custom_board: &mut CustomBoard,
+) {
+    match button {
+        // The board plugin resumes the saved game
+        MenuButton::Continue => next_state.set(AppState::InGame),
+        MenuButton::WatchReplay => {
+            let Some(path) = board_options.replay_path.as_ref() else { return };
+            match Replay::load(path) {
+                Ok(replay) => {
+                    commands.insert_resource(ReplayPlayer::new(replay));
+                    next_state.set(AppState::InGame);
+                }
+                Err(e) => error!("Failed to load replay from {}: {}", path.display(), e),
+            }
+        }
+        MenuButton::Preset(preset) => {
+            board_options.apply_preset(preset);
+            start_new_game(next_state, board_options);
+        }
+        MenuButton::Adjust(field, delta) => custom_board.adjust(field, delta),
+        MenuButton::StartCustom => {
+            if let Ok(options) = custom_board.board_options(board_options) {
+                *board_options = options;
+                start_new_game(next_state, board_options);
+            }
+        }
+    }
+}
+
+/// Drops the saved game, which would be resumed instead of the chosen board
+fn start_new_game(next_state: &mut NextState<AppState>, board_options: &BoardOptions<AppState>) {
+    BoardPlugin::<AppState>::remove_save(board_options);
+    next_state.set(AppState::InGame);
+}
+
+pub fn update_custom_form(
+    board_options: Res<BoardOptions<AppState>>,
+    custom_board: Res<CustomBoard>,
+    mut field_query: Query<(&mut Text, &CustomFieldText), Without<CustomErrorText>>,
+    mut error_query: Query<&mut Text, With<CustomErrorText>>,
+) {
+    if !custom_board.is_changed() {
+        return;
+    }
+    for (mut text, field) in field_query.iter_mut() {
+        text.sections[0].value = custom_board.value(field.0).to_string();
+    }
+    let error = custom_board.error_message(&board_options);
+    for mut text in error_query.iter_mut() {
+        text.sections[0].value = error.clone();
+    }
+}
+
+/// Leaves the board to the main menu
+pub fn back_to_menu(keys: Res<ButtonInput<KeyCode>>, mut next_state: ResMut<NextState<AppState>>) {
+    if keys.just_released(KeyCode::Escape) {
+        next_state.set(AppState::Menu);
+    }
+}